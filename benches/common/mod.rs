@@ -0,0 +1,20 @@
+use advent_of_code::permutations::PermutationsIterator;
+use advent_of_code::program::Program;
+
+/// Counts down from 100000 in a loop of two instructions
+pub const COUNT_DOWN: [i64; 9] = [1001, 8, -1, 8, 1005, 8, 0, 99, 100_000];
+
+pub fn load_day_seven_program() -> Vec<i64> {
+    Program::from_file("data/day-seven-input.txt")
+        .expect("Failed to load the day seven program")
+        .into_words()
+}
+
+/// Runs every ordering of the phases through a chain of five amplifiers, which runs the program
+/// 600 times
+pub fn search_amplifiers(run: impl Fn(i64, i64) -> i64) -> i64 {
+    PermutationsIterator::from(vec![0, 1, 2, 3, 4])
+        .map(|phases| phases.iter().fold(0, |signal, &phase| run(phase, signal)))
+        .max()
+        .expect("No permutations")
+}
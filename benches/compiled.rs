@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use advent_of_code::intcode_computer::{CompiledProgram, IntcodeComputer};
+
+mod common;
+
+use common::{load_day_seven_program, search_amplifiers, COUNT_DOWN};
+
+fn interpret(program: &[i64], phase: i64, signal: i64) -> i64 {
+    let mut computer = IntcodeComputer::new(program.to_vec());
+    computer.push_input(phase);
+    computer.push_input(signal);
+    computer.run_to_halt().expect("Failed to run an amplifier")[0]
+}
+
+fn run_compiled(program: &Arc<CompiledProgram>, phase: i64, signal: i64) -> i64 {
+    let mut computer = program.instantiate();
+    computer.push_input(phase);
+    computer.push_input(signal);
+    computer.run_to_halt().expect("Failed to run an amplifier")[0]
+}
+
+fn compiled(c: &mut Criterion) {
+    let program = load_day_seven_program();
+    let compiled = Arc::new(CompiledProgram::compile(&program));
+    let mut group = c.benchmark_group("amplifier search");
+    group.bench_function("interpreted", |b| {
+        b.iter(|| search_amplifiers(|phase, signal| interpret(&program, phase, signal)))
+    });
+    group.bench_function("compiled", |b| {
+        b.iter(|| search_amplifiers(|phase, signal| run_compiled(&compiled, phase, signal)))
+    });
+    group.finish();
+
+    let compiled = Arc::new(CompiledProgram::compile(&COUNT_DOWN));
+    let mut group = c.benchmark_group("count down");
+    group.bench_function("interpreted", |b| {
+        b.iter(|| IntcodeComputer::new(COUNT_DOWN.to_vec()).execute())
+    });
+    group.bench_function("compiled", |b| b.iter(|| compiled.instantiate().execute()));
+    group.finish();
+}
+
+criterion_group!(benches, compiled);
+criterion_main!(benches);
@@ -0,0 +1,111 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use advent_of_code::intcode_computer::{ExecutionState, IntcodeComputer};
+use advent_of_code::permutations::PermutationsIterator;
+
+mod common;
+
+use common::{load_day_seven_program, search_amplifiers, COUNT_DOWN};
+
+#[derive(Debug, Copy, Clone)]
+enum Decoding {
+    Uncached,
+    Cached,
+    Predecoded,
+}
+
+impl Decoding {
+    fn name(self) -> String {
+        format!("{:?}", self).to_lowercase()
+    }
+}
+
+/// The machine that every amplifier is cloned from
+fn template(program: &[i64], decoding: Decoding) -> IntcodeComputer {
+    let mut computer = IntcodeComputer::new(program.to_vec());
+    match decoding {
+        Decoding::Uncached => {}
+        Decoding::Cached => computer.enable_decode_cache(),
+        Decoding::Predecoded => computer.predecode(),
+    }
+    computer
+}
+
+fn amplifier(template: &IntcodeComputer, phase: i64) -> IntcodeComputer {
+    let mut computer = template.clone();
+    computer.push_input(phase);
+    computer
+}
+
+/// Searches the amplifier chains with every amplifier cloned from the same template
+fn search_amplifier_chains(program: &[i64], decoding: Decoding) -> i64 {
+    let template = template(program, decoding);
+    search_amplifiers(|phase, signal| {
+        let mut computer = amplifier(&template, phase);
+        computer.push_input(signal);
+        let output = computer.run_to_halt().expect("Failed to run an amplifier");
+        output[0]
+    })
+}
+
+/// Runs every ordering of the phases through five amplifiers that feed back into each other
+fn search_feedback_loops(program: &[i64], decoding: Decoding) -> i64 {
+    let template = template(program, decoding);
+    PermutationsIterator::from(vec![5, 6, 7, 8, 9])
+        .map(|phases| {
+            let mut amplifiers: Vec<_> = phases
+                .iter()
+                .map(|&phase| amplifier(&template, phase))
+                .collect();
+            let mut signal = 0;
+            for index in (0..amplifiers.len()).cycle() {
+                amplifiers[index].push_input(signal);
+                match amplifiers[index].execute_until_output() {
+                    Ok(ExecutionState::ProducedOutput(output)) => signal = output,
+                    Ok(ExecutionState::Halted) if index == amplifiers.len() - 1 => break,
+                    Ok(ExecutionState::Halted) => {}
+                    state => panic!("Unexpected amplifier state {:?}", state),
+                }
+            }
+            signal
+        })
+        .max()
+        .expect("No permutations")
+}
+
+fn count_down(decoding: Decoding) -> u64 {
+    let mut computer = template(&COUNT_DOWN, decoding);
+    computer.execute().expect("Failed to count down");
+    computer.steps()
+}
+
+fn decode_cache(c: &mut Criterion) {
+    let program = load_day_seven_program();
+    let decodings = [Decoding::Uncached, Decoding::Cached, Decoding::Predecoded];
+
+    let mut group = c.benchmark_group("day seven");
+    for &decoding in &decodings {
+        group.bench_with_input(
+            BenchmarkId::new("amplifier search", decoding.name()),
+            &program,
+            |b, program| b.iter(|| search_amplifier_chains(program, decoding)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("feedback loop search", decoding.name()),
+            &program,
+            |b, program| b.iter(|| search_feedback_loops(program, decoding)),
+        );
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("tight loop");
+    for &decoding in &decodings {
+        group.bench_function(BenchmarkId::new("count down", decoding.name()), |b| {
+            b.iter(|| count_down(decoding))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decode_cache);
+criterion_main!(benches);
@@ -0,0 +1,833 @@
+use thiserror::Error;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use num::{CheckedAdd, CheckedSub};
+
+use crate::intcode_computer::{Operation, ParameterMode, Word};
+
+#[derive(Debug, PartialEq, Error)]
+#[error("Line {line}, column {column}: {kind}")]
+pub struct AssemblyError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: AssemblyErrorKind,
+}
+
+#[derive(Debug, PartialEq, Error)]
+pub enum AssemblyErrorKind {
+    #[error("Unknown mnemonic {0}")]
+    UnknownMnemonic(String),
+    #[error("Unknown directive {0}")]
+    UnknownDirective(String),
+    #[error("Expected {expected} operands, but found {found}")]
+    WrongNumberOfOperands { expected: usize, found: usize },
+    #[error("Output parameters can not use immediate mode")]
+    ImmediateOutput,
+    #[error("Undefined symbol {0}")]
+    UndefinedSymbol(String),
+    #[error("Constant {0} is defined in terms of itself")]
+    CircularDefinition(String),
+    #[error("Symbol {0} is already defined")]
+    DuplicateSymbol(String),
+    #[error("Invalid number {0}")]
+    InvalidNumber(String),
+    #[error("Unterminated string literal")]
+    UnterminatedString,
+    #[error("Invalid escape sequence \\{0}")]
+    InvalidEscape(char),
+    #[error("Unexpected {0}")]
+    UnexpectedToken(String),
+    #[error("Unexpected end of line")]
+    UnexpectedEndOfLine,
+}
+
+/// Assembles mnemonic source into a program image.
+///
+/// Each line holds any number of `label:` definitions followed by an instruction or a directive,
+/// and everything after a `;` is a comment. Instructions are written as a mnemonic (`ADD`, `MUL`,
+/// `IN`, `OUT`, `JNZ`, `JZ`, `LT`, `EQ`, `ARB` or `HLT`) followed by comma separated parameters,
+/// which are `#value` for immediate mode, `[address]` for position mode and `[rb+offset]` for
+/// relative mode. Values are sums and differences of numbers, character literals, labels and
+/// constants. The directives are `.data` for raw words, `.string` for the character codes of a
+/// string literal and `.const NAME = value` for naming a value.
+pub fn assemble<W: Word + FromStr + CheckedAdd + CheckedSub>(
+    source: &str,
+) -> Result<Vec<W>, AssemblyError> {
+    let mut statements = Vec::new();
+    let mut symbols = HashMap::new();
+    let mut address = 0;
+    for (index, text) in source.lines().enumerate() {
+        let line = index + 1;
+        let tokens =
+            tokenize(text).map_err(|(column, kind)| AssemblyError { line, column, kind })?;
+        let (labels, statement) =
+            parse_line(&tokens).map_err(|(column, kind)| AssemblyError { line, column, kind })?;
+        for (label, column) in labels {
+            define(&mut symbols, label, Symbol::Label(address), line, column)?;
+        }
+        match statement {
+            Some(Statement::Constant {
+                name,
+                column,
+                value,
+            }) => {
+                define(&mut symbols, name, Symbol::Constant(value), line, column)?;
+            }
+            Some(statement) => {
+                address += statement.size();
+                statements.push((line, statement));
+            }
+            None => {}
+        }
+    }
+
+    let mut program = Vec::with_capacity(address);
+    for (line, statement) in statements {
+        statement
+            .encode(&symbols, &mut program)
+            .map_err(|(column, kind)| AssemblyError { line, column, kind })?;
+    }
+    Ok(program)
+}
+
+fn define(
+    symbols: &mut HashMap<String, Symbol>,
+    name: String,
+    symbol: Symbol,
+    line: usize,
+    column: usize,
+) -> Result<(), AssemblyError> {
+    if symbols.contains_key(&name) || name.eq_ignore_ascii_case("rb") {
+        return Err(AssemblyError {
+            line,
+            column,
+            kind: AssemblyErrorKind::DuplicateSymbol(name),
+        });
+    }
+    symbols.insert(name, symbol);
+    Ok(())
+}
+
+type Failure = (usize, AssemblyErrorKind);
+
+#[derive(Debug, PartialEq, Clone)]
+enum TokenKind {
+    Identifier(String),
+    Number(String),
+    Character(char),
+    Text(String),
+    Symbol(char),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct Token {
+    kind: TokenKind,
+    column: usize,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match &self.kind {
+            TokenKind::Identifier(name) => name.clone(),
+            TokenKind::Number(number) => number.clone(),
+            TokenKind::Character(character) => format!("{:?}", character),
+            TokenKind::Text(text) => format!("{:?}", text),
+            TokenKind::Symbol(symbol) => symbol.to_string(),
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, Failure> {
+    let characters: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while index < characters.len() {
+        let character = characters[index];
+        let column = index + 1;
+        if character == ';' {
+            break;
+        } else if character.is_whitespace() {
+            index += 1;
+        } else if character.is_ascii_digit() {
+            let end = scan(&characters, index, |c| {
+                c.is_ascii_alphanumeric() || c == '_'
+            });
+            let number = characters[index..end].iter().collect();
+            tokens.push(Token {
+                kind: TokenKind::Number(number),
+                column,
+            });
+            index = end;
+        } else if character.is_alphabetic() || character == '_' || character == '.' {
+            let end = scan(&characters, index + 1, |c| c.is_alphanumeric() || c == '_');
+            let name = characters[index..end].iter().collect();
+            tokens.push(Token {
+                kind: TokenKind::Identifier(name),
+                column,
+            });
+            index = end;
+        } else if character == '"' || character == '\'' {
+            let mut contents = String::new();
+            index += 1;
+            loop {
+                match characters.get(index) {
+                    None => return Err((column, AssemblyErrorKind::UnterminatedString)),
+                    Some(c) if *c == character => break,
+                    Some('\\') => {
+                        let escaped = characters
+                            .get(index + 1)
+                            .ok_or((column, AssemblyErrorKind::UnterminatedString))?;
+                        contents.push(match escaped {
+                            'n' => '\n',
+                            't' => '\t',
+                            '0' => '\0',
+                            '\\' | '\'' | '"' => *escaped,
+                            c => return Err((index + 1, AssemblyErrorKind::InvalidEscape(*c))),
+                        });
+                        index += 2;
+                    }
+                    Some(c) => {
+                        contents.push(*c);
+                        index += 1;
+                    }
+                }
+            }
+            index += 1;
+            let kind = if character == '"' {
+                TokenKind::Text(contents)
+            } else {
+                let mut contents = contents.chars();
+                match (contents.next(), contents.next()) {
+                    (Some(c), None) => TokenKind::Character(c),
+                    _ => {
+                        return Err((
+                            column,
+                            AssemblyErrorKind::UnexpectedToken(String::from("character literal")),
+                        ))
+                    }
+                }
+            };
+            tokens.push(Token { kind, column });
+        } else if ":,#[]+-=".contains(character) {
+            tokens.push(Token {
+                kind: TokenKind::Symbol(character),
+                column,
+            });
+            index += 1;
+        } else {
+            return Err((
+                column,
+                AssemblyErrorKind::UnexpectedToken(character.to_string()),
+            ));
+        }
+    }
+    Ok(tokens)
+}
+
+fn scan(characters: &[char], start: usize, predicate: impl Fn(char) -> bool) -> usize {
+    characters[start..]
+        .iter()
+        .position(|c| !predicate(*c))
+        .map(|offset| start + offset)
+        .unwrap_or_else(|| characters.len())
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Term {
+    Number(String),
+    Character(char),
+    Symbol(String),
+}
+
+/// A sum of terms, each of which is either added or subtracted, along with the column it was
+/// found at
+#[derive(Debug, PartialEq, Clone)]
+struct Expression(Vec<(bool, Term, usize)>);
+
+#[derive(Debug, PartialEq, Clone)]
+struct Parameter {
+    mode: ParameterMode,
+    value: Expression,
+    column: usize,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Statement {
+    Instruction {
+        mnemonic: String,
+        column: usize,
+        parameters: Vec<Parameter>,
+    },
+    Data(Vec<Expression>),
+    Text(String),
+    Constant {
+        name: String,
+        column: usize,
+        value: Expression,
+    },
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Symbol {
+    Label(usize),
+    Constant(Expression),
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    index: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.index)
+    }
+
+    fn peek_symbol(&self, symbol: char) -> bool {
+        matches!(self.peek(), Some(Token { kind: TokenKind::Symbol(s), .. }) if *s == symbol)
+    }
+
+    fn next(&mut self) -> Result<&'a Token, Failure> {
+        let token = self.tokens.get(self.index).ok_or_else(|| {
+            (
+                self.tokens.last().map(|t| t.column + 1).unwrap_or(1),
+                AssemblyErrorKind::UnexpectedEndOfLine,
+            )
+        })?;
+        self.index += 1;
+        Ok(token)
+    }
+
+    fn expect_symbol(&mut self, symbol: char) -> Result<(), Failure> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Symbol(s) if s == symbol => Ok(()),
+            _ => Err(unexpected(token)),
+        }
+    }
+
+    fn expect_end(&self) -> Result<(), Failure> {
+        match self.peek() {
+            Some(token) => Err(unexpected(token)),
+            None => Ok(()),
+        }
+    }
+
+    fn list<T>(
+        &mut self,
+        item: impl Fn(&mut Self) -> Result<T, Failure>,
+    ) -> Result<Vec<T>, Failure> {
+        let mut items = Vec::new();
+        if self.peek().is_none() {
+            return Ok(items);
+        }
+        items.push(item(self)?);
+        while self.peek_symbol(',') {
+            self.index += 1;
+            items.push(item(self)?);
+        }
+        self.expect_end()?;
+        Ok(items)
+    }
+
+    fn expression(&mut self) -> Result<Expression, Failure> {
+        let mut terms = Vec::new();
+        let mut positive = true;
+        if self.peek_symbol('+') || self.peek_symbol('-') {
+            positive = self.peek_symbol('+');
+            self.index += 1;
+        }
+        loop {
+            let token = self.next()?;
+            let term = match &token.kind {
+                TokenKind::Number(number) => Term::Number(number.clone()),
+                TokenKind::Character(character) => Term::Character(*character),
+                TokenKind::Identifier(name) if !name.eq_ignore_ascii_case("rb") => {
+                    Term::Symbol(name.clone())
+                }
+                _ => return Err(unexpected(token)),
+            };
+            terms.push((positive, term, token.column));
+            if self.peek_symbol('+') || self.peek_symbol('-') {
+                positive = self.peek_symbol('+');
+                self.index += 1;
+            } else {
+                return Ok(Expression(terms));
+            }
+        }
+    }
+
+    fn parameter(&mut self) -> Result<Parameter, Failure> {
+        let token = self.next()?;
+        let column = token.column;
+        match token.kind {
+            TokenKind::Symbol('#') => Ok(Parameter {
+                mode: ParameterMode::Immediate,
+                value: self.expression()?,
+                column,
+            }),
+            TokenKind::Symbol('[') => {
+                let relative = matches!(
+                    self.peek(),
+                    Some(Token { kind: TokenKind::Identifier(name), .. }) if name.eq_ignore_ascii_case("rb")
+                );
+                let parameter = if relative {
+                    self.index += 1;
+                    let value = if self.peek_symbol(']') {
+                        Expression(vec![(true, Term::Number(String::from("0")), column)])
+                    } else if self.peek_symbol('+') || self.peek_symbol('-') {
+                        self.expression()?
+                    } else {
+                        return Err(unexpected(self.next()?));
+                    };
+                    Parameter {
+                        mode: ParameterMode::Relative,
+                        value,
+                        column,
+                    }
+                } else {
+                    Parameter {
+                        mode: ParameterMode::Position,
+                        value: self.expression()?,
+                        column,
+                    }
+                };
+                self.expect_symbol(']')?;
+                Ok(parameter)
+            }
+            _ => Err(unexpected(token)),
+        }
+    }
+}
+
+fn unexpected(token: &Token) -> Failure {
+    (
+        token.column,
+        AssemblyErrorKind::UnexpectedToken(token.describe()),
+    )
+}
+
+/// The labels defined on a line, each with the column it was found at, and its statement
+type ParsedLine = (Vec<(String, usize)>, Option<Statement>);
+
+fn parse_line(tokens: &[Token]) -> Result<ParsedLine, Failure> {
+    let mut parser = Parser { tokens, index: 0 };
+    let mut labels = Vec::new();
+    while let (
+        Some(token),
+        Some(Token {
+            kind: TokenKind::Symbol(':'),
+            ..
+        }),
+    ) = (parser.peek(), tokens.get(parser.index + 1))
+    {
+        match &token.kind {
+            TokenKind::Identifier(name) if !name.starts_with('.') => {
+                labels.push((name.clone(), token.column))
+            }
+            _ => return Err(unexpected(token)),
+        }
+        parser.index += 2;
+    }
+
+    let token = match parser.peek() {
+        Some(token) => token,
+        None => return Ok((labels, None)),
+    };
+    parser.index += 1;
+    let name = match &token.kind {
+        TokenKind::Identifier(name) => name.to_ascii_lowercase(),
+        _ => return Err(unexpected(token)),
+    };
+    let statement = match name.as_str() {
+        ".data" | ".word" => Statement::Data(parser.list(Parser::expression)?),
+        ".string" => {
+            let text = parser.next()?;
+            parser.expect_end()?;
+            match &text.kind {
+                TokenKind::Text(text) => Statement::Text(text.clone()),
+                _ => return Err(unexpected(text)),
+            }
+        }
+        ".const" | ".equ" => {
+            let constant = parser.next()?;
+            let name = match &constant.kind {
+                TokenKind::Identifier(name) if !name.starts_with('.') => name.clone(),
+                _ => return Err(unexpected(constant)),
+            };
+            parser.expect_symbol('=')?;
+            let value = parser.expression()?;
+            parser.expect_end()?;
+            Statement::Constant {
+                name,
+                column: constant.column,
+                value,
+            }
+        }
+        directive if directive.starts_with('.') => {
+            return Err((
+                token.column,
+                AssemblyErrorKind::UnknownDirective(directive.to_string()),
+            ))
+        }
+        mnemonic => {
+            if arity(mnemonic).is_none() {
+                return Err((
+                    token.column,
+                    AssemblyErrorKind::UnknownMnemonic(mnemonic.to_ascii_uppercase()),
+                ));
+            }
+            Statement::Instruction {
+                mnemonic: mnemonic.to_string(),
+                column: token.column,
+                parameters: parser.list(Parser::parameter)?,
+            }
+        }
+    };
+    Ok((labels, Some(statement)))
+}
+
+fn arity(mnemonic: &str) -> Option<usize> {
+    match mnemonic {
+        "add" | "mul" | "lt" | "eq" => Some(3),
+        "jnz" | "jt" | "jz" | "jf" => Some(2),
+        "in" | "out" | "arb" => Some(1),
+        "hlt" => Some(0),
+        _ => None,
+    }
+}
+
+fn operation_for(mnemonic: &str, modes: &[ParameterMode]) -> Option<Operation> {
+    let operation = match (mnemonic, modes) {
+        ("add", &[left, right, output]) => Operation::Add(left, right, output),
+        ("mul", &[left, right, output]) => Operation::Multiply(left, right, output),
+        ("in", &[output]) => Operation::StoreInput(output),
+        ("out", &[mode]) => Operation::ProduceOutput(mode),
+        ("jnz", &[condition, target]) | ("jt", &[condition, target]) => {
+            Operation::JumpIfTrue(condition, target)
+        }
+        ("jz", &[condition, target]) | ("jf", &[condition, target]) => {
+            Operation::JumpIfFalse(condition, target)
+        }
+        ("lt", &[left, right, output]) => Operation::LessThan(left, right, output),
+        ("eq", &[left, right, output]) => Operation::EqualTo(left, right, output),
+        ("arb", &[mode]) => Operation::AdjustRelativeBase(mode),
+        ("hlt", &[]) => Operation::Terminate,
+        _ => return None,
+    };
+    Some(operation)
+}
+
+fn is_output_parameter(mnemonic: &str, index: usize) -> bool {
+    match mnemonic {
+        "add" | "mul" | "lt" | "eq" => index == 2,
+        "in" => index == 0,
+        _ => false,
+    }
+}
+
+impl Statement {
+    fn size(&self) -> usize {
+        match self {
+            Statement::Instruction { parameters, .. } => 1 + parameters.len(),
+            Statement::Data(values) => values.len(),
+            Statement::Text(text) => text.chars().count(),
+            Statement::Constant { .. } => 0,
+        }
+    }
+
+    fn encode<W: Word + FromStr + CheckedAdd + CheckedSub>(
+        &self,
+        symbols: &HashMap<String, Symbol>,
+        program: &mut Vec<W>,
+    ) -> Result<(), Failure> {
+        match self {
+            Statement::Instruction {
+                mnemonic,
+                column,
+                parameters,
+            } => {
+                let expected = arity(mnemonic).unwrap_or(0);
+                if parameters.len() != expected {
+                    return Err((
+                        *column,
+                        AssemblyErrorKind::WrongNumberOfOperands {
+                            expected,
+                            found: parameters.len(),
+                        },
+                    ));
+                }
+                for (index, parameter) in parameters.iter().enumerate() {
+                    if parameter.mode == ParameterMode::Immediate
+                        && is_output_parameter(mnemonic, index)
+                    {
+                        return Err((parameter.column, AssemblyErrorKind::ImmediateOutput));
+                    }
+                }
+                let modes: Vec<ParameterMode> = parameters.iter().map(|p| p.mode).collect();
+                let operation = operation_for(mnemonic, &modes).ok_or_else(|| {
+                    (
+                        *column,
+                        AssemblyErrorKind::UnknownMnemonic(mnemonic.to_ascii_uppercase()),
+                    )
+                })?;
+                program.push(W::from(operation.encode()));
+                for parameter in parameters {
+                    program.push(evaluate(&parameter.value, symbols, &mut Vec::new())?);
+                }
+            }
+            Statement::Data(values) => {
+                for value in values {
+                    program.push(evaluate(value, symbols, &mut Vec::new())?);
+                }
+            }
+            Statement::Text(text) => {
+                program.extend(text.chars().map(|c| W::from(c as i32)));
+            }
+            Statement::Constant { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+/// Evaluates an expression, keeping track of the constants being evaluated to detect cycles
+fn evaluate<W: Word + FromStr + CheckedAdd + CheckedSub>(
+    expression: &Expression,
+    symbols: &HashMap<String, Symbol>,
+    evaluating: &mut Vec<String>,
+) -> Result<W, Failure> {
+    let mut total = W::zero();
+    for (positive, term, column) in &expression.0 {
+        let value = match term {
+            Term::Number(number) => number
+                .parse()
+                .map_err(|_| (*column, AssemblyErrorKind::InvalidNumber(number.clone())))?,
+            Term::Character(character) => W::from(*character as i32),
+            Term::Symbol(name) => match symbols.get(name) {
+                Some(Symbol::Label(address)) => W::from_usize(*address)
+                    .ok_or_else(|| (*column, AssemblyErrorKind::InvalidNumber(name.clone())))?,
+                Some(Symbol::Constant(_)) if evaluating.contains(name) => {
+                    return Err((*column, AssemblyErrorKind::CircularDefinition(name.clone())))
+                }
+                Some(Symbol::Constant(value)) => {
+                    evaluating.push(name.clone());
+                    // errors inside of a constant are reported where the constant is used
+                    let value = evaluate(value, symbols, evaluating)
+                        .map_err(|(_, kind)| (*column, kind))?;
+                    evaluating.pop();
+                    value
+                }
+                None => return Err((*column, AssemblyErrorKind::UndefinedSymbol(name.clone()))),
+            },
+        };
+        let sum = if *positive {
+            total.checked_add(&value)
+        } else {
+            total.checked_sub(&value)
+        };
+        total = sum.ok_or_else(|| {
+            let text = match term {
+                Term::Number(text) | Term::Symbol(text) => text.clone(),
+                Term::Character(character) => format!("'{}'", character),
+            };
+            (*column, AssemblyErrorKind::InvalidNumber(text))
+        })?;
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::program::Program;
+
+    use crate::disassembler::{disassemble, DisassemblerOptions, Line};
+    use crate::intcode_computer::IntcodeComputer;
+
+    use std::collections::VecDeque;
+
+    #[test]
+    fn it_should_assemble_instructions() {
+        let program: Vec<i64> = assemble(
+            "
+            in   [9]
+            mul  [9], #3, [rb+2]
+            out  [rb-1]
+            hlt
+            ",
+        )
+        .expect("Failed to assemble");
+        assert_eq!(vec![3, 9, 21002, 9, 3, 2, 204, -1, 99], program);
+    }
+
+    #[test]
+    fn it_should_resolve_labels_and_constants() {
+        let program: Vec<i64> = assemble(
+            "
+            .const LIMIT = 3
+            .const START = LIMIT - 3 + 'A'
+            loop:   OUT  [counter]             ; print the counter
+                    ADD  [counter], #1, [counter]
+                    LT   [counter], #START+LIMIT, [flag]
+                    JNZ  [flag], #loop
+            end:    HLT
+            counter: .data START
+            flag:    .data 0, end
+            ",
+        )
+        .expect("Failed to assemble");
+        assert_eq!(
+            vec![4, 14, 1001, 14, 1, 14, 1007, 14, 68, 15, 1005, 15, 0, 99, 65, 0, 13],
+            program[..]
+        );
+        let mut computer = IntcodeComputer::new(program);
+        let output = computer.run_to_halt().expect("Failed to execute program");
+        assert_eq!(VecDeque::from(vec![65, 66, 67]), output);
+    }
+
+    #[test]
+    fn it_should_assemble_strings() {
+        let program: Vec<i64> = assemble(
+            r#"
+            start: OUT [rb+message]
+                   ARB #1
+                   EQ  [rb+message], #0, [done]
+                   JZ  [done], #start
+                   HLT
+            done:  .data 0
+            message: .string "Hi\n"
+                     .data 0
+            "#,
+        )
+        .expect("Failed to assemble");
+        let mut computer = IntcodeComputer::new(program);
+        let output = computer.run_to_halt().expect("Failed to execute program");
+        assert_eq!(VecDeque::from(vec![72, 105, 10]), output);
+    }
+
+    #[test]
+    fn it_should_reassemble_disassembled_instructions() {
+        let program: Vec<i64> = include_str!("../data/day-five-input.txt")
+            .parse::<Program>()
+            .expect("Invalid program")
+            .into_words();
+        let disassembly = disassemble(&program, &DisassemblerOptions::default());
+        for line in disassembly.lines {
+            if let Line::Instruction(instruction) = line {
+                let words: Vec<i64> =
+                    assemble(&instruction.to_string()).expect("Failed to assemble");
+                assert_eq!(
+                    program[instruction.address..instruction.next_address()].to_vec(),
+                    words
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn it_should_report_errors_with_lines_and_columns() {
+        let error = |source: &str| assemble::<i64>(source).expect_err("Failed to fail");
+        assert_eq!(
+            AssemblyError {
+                line: 2,
+                column: 5,
+                kind: AssemblyErrorKind::UnknownMnemonic(String::from("JMP"))
+            },
+            error("HLT\n    jmp #0")
+        );
+        assert_eq!(
+            AssemblyError {
+                line: 1,
+                column: 17,
+                kind: AssemblyErrorKind::ImmediateOutput
+            },
+            error("ADD #1, [rb+2], #3")
+        );
+        assert_eq!(
+            AssemblyError {
+                line: 1,
+                column: 1,
+                kind: AssemblyErrorKind::WrongNumberOfOperands {
+                    expected: 1,
+                    found: 2
+                }
+            },
+            error("OUT #1, #2")
+        );
+        assert_eq!(
+            AssemblyError {
+                line: 1,
+                column: 10,
+                kind: AssemblyErrorKind::UndefinedSymbol(String::from("nowhere"))
+            },
+            error("JNZ #1, #nowhere")
+        );
+        assert_eq!(
+            AssemblyError {
+                line: 3,
+                column: 1,
+                kind: AssemblyErrorKind::DuplicateSymbol(String::from("a"))
+            },
+            error("a: HLT\n\na: HLT")
+        );
+        assert_eq!(
+            AssemblyError {
+                line: 1,
+                column: 9,
+                kind: AssemblyErrorKind::UnterminatedString
+            },
+            error(".string \"open")
+        );
+        assert_eq!(
+            AssemblyError {
+                line: 1,
+                column: 7,
+                kind: AssemblyErrorKind::InvalidNumber(String::from("12ab"))
+            },
+            error(".data 12ab")
+        );
+        assert_eq!(
+            AssemblyError {
+                line: 1,
+                column: 27,
+                kind: AssemblyErrorKind::InvalidNumber(String::from("1"))
+            },
+            error(".data 9223372036854775807+1")
+        );
+        assert_eq!(
+            AssemblyError {
+                line: 2,
+                column: 13,
+                kind: AssemblyErrorKind::InvalidNumber(String::from("HIGH"))
+            },
+            error(".const HIGH = 9223372036854775807\nJNZ #0, #-2-HIGH")
+        );
+        assert_eq!(
+            AssemblyError {
+                line: 1,
+                column: 1,
+                kind: AssemblyErrorKind::UnknownDirective(String::from(".org"))
+            },
+            error(".org 100")
+        );
+        assert_eq!(
+            AssemblyError {
+                line: 1,
+                column: 5,
+                kind: AssemblyErrorKind::UnexpectedToken(String::from("7"))
+            },
+            error("OUT 7")
+        );
+        assert_eq!(
+            AssemblyError {
+                line: 3,
+                column: 7,
+                kind: AssemblyErrorKind::CircularDefinition(String::from("A"))
+            },
+            error(".const A = B\n.const B = A\n.data A")
+        );
+    }
+}
@@ -1,13 +1,14 @@
 use std::collections::VecDeque;
 
 use advent_of_code::intcode_computer::{ExecutionError, IntcodeComputer};
+use advent_of_code::program::Program;
 
 fn main() {
     let puzzle_input = include_str!("../../data/day-five-input.txt");
-    let program: Vec<i32> = puzzle_input
-        .split(',')
-        .flat_map(|l| i32::from_str_radix(l, 10).into_iter())
-        .collect();
+    let program: Vec<i64> = puzzle_input
+        .parse::<Program>()
+        .expect("Invalid puzzle input")
+        .into_words();
     let output = run_diagnostic(program, VecDeque::from(vec![5]));
     println!("Result: {:?}", output);
 }
@@ -15,15 +16,15 @@ fn main() {
 #[derive(Debug, PartialEq)]
 enum DiagnosticResult {
     EmptyOutput,
-    Success { code: i32, output: VecDeque<i32> },
-    Failure { code: i32, output: VecDeque<i32> },
+    Success { code: i64, output: VecDeque<i64> },
+    Failure { code: i64, output: VecDeque<i64> },
     Error(ExecutionError),
 }
 
-fn run_diagnostic(program: Vec<i32>, input: VecDeque<i32>) -> DiagnosticResult {
+fn run_diagnostic(program: Vec<i64>, input: VecDeque<i64>) -> DiagnosticResult {
     let mut computer = IntcodeComputer::new_with_input(program, input);
     computer
-        .execute()
+        .run_to_halt()
         .map(|mut output| {
             if let Some(diagnostic_code) = output.pop_back() {
                 if output.iter().copied().all(|x| x == 0) {
@@ -76,10 +77,10 @@ mod test {
     fn it_should_proxy_execution_errors() {
         assert_eq!(
             DiagnosticResult::Error(ExecutionError::IndexOutsideOfProgram {
-                index: 3,
-                program_length: 3
+                index: -1,
+                program_length: 4
             }),
-            run_diagnostic(vec![1, 0, 0], VecDeque::new())
+            run_diagnostic(vec![1, -1, 0, 0], VecDeque::new())
         );
     }
 
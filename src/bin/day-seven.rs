@@ -2,39 +2,74 @@ use anyhow::anyhow;
 
 use std::collections::VecDeque;
 
-use advent_of_code::intcode_computer::IntcodeComputer;
+use advent_of_code::intcode_computer::{ExecutionState, IntcodeComputer};
 use advent_of_code::permutations::PermutationsIterator;
+use advent_of_code::program::Program;
 
 fn main() {
     let puzzle_input = include_str!("../../data/day-seven-input.txt");
-    let program: Vec<i32> = puzzle_input
-        .split(',')
-        .flat_map(|l| i32::from_str_radix(l, 10).into_iter())
-        .collect();
+    let program: Vec<i64> = puzzle_input
+        .parse::<Program>()
+        .expect("Invalid puzzle input")
+        .into_words();
     let (value, setting) =
-        maximize_amplifier_output(program).expect("Failed to execute phase settings");
+        maximize_amplifier_output(program.clone()).expect("Failed to execute phase settings");
     println!("Got {}, for setting {:?}", value, setting);
+    let (value, setting) =
+        maximize_feedback_loop_output(program).expect("Failed to execute phase settings");
+    println!(
+        "Got {} with a feedback loop, for setting {:?}",
+        value, setting
+    );
 }
 
-fn maximize_amplifier_output(program: Vec<i32>) -> Result<(i32, [PhaseSetting; 5]), anyhow::Error> {
-    PermutationsIterator::from(vec![
-        PhaseSetting::Zero,
-        PhaseSetting::One,
-        PhaseSetting::Two,
-        PhaseSetting::Three,
-        PhaseSetting::Four,
-    ])
-    .map(|p| [p[0], p[1], p[2], p[3], p[4]])
-    .map(|p| (evaluate_sequence_for_program(program.clone(), p), p))
-    .filter_map(|(value, p)| {
-        if let Ok(value) = value {
-            Some((value, p))
-        } else {
-            None
-        }
-    })
-    .max_by_key(|(value, _)| *value)
-    .ok_or_else(|| anyhow!("Did not get a maximum value"))
+fn maximize_amplifier_output(program: Vec<i64>) -> Result<(i64, [PhaseSetting; 5]), anyhow::Error> {
+    maximize_output(
+        program,
+        [
+            PhaseSetting::Zero,
+            PhaseSetting::One,
+            PhaseSetting::Two,
+            PhaseSetting::Three,
+            PhaseSetting::Four,
+        ],
+        evaluate_sequence_for_program,
+    )
+}
+
+fn maximize_feedback_loop_output(
+    program: Vec<i64>,
+) -> Result<(i64, [PhaseSetting; 5]), anyhow::Error> {
+    maximize_output(
+        program,
+        [
+            PhaseSetting::Five,
+            PhaseSetting::Six,
+            PhaseSetting::Seven,
+            PhaseSetting::Eight,
+            PhaseSetting::Nine,
+        ],
+        evaluate_feedback_loop_for_program,
+    )
+}
+
+fn maximize_output(
+    program: Vec<i64>,
+    phase_settings: [PhaseSetting; 5],
+    evaluate: impl Fn(Vec<i64>, [PhaseSetting; 5]) -> Result<i64, anyhow::Error>,
+) -> Result<(i64, [PhaseSetting; 5]), anyhow::Error> {
+    PermutationsIterator::from(phase_settings.to_vec())
+        .map(|p| [p[0], p[1], p[2], p[3], p[4]])
+        .map(|p| (evaluate(program.clone(), p), p))
+        .filter_map(|(value, p)| {
+            if let Ok(value) = value {
+                Some((value, p))
+            } else {
+                None
+            }
+        })
+        .max_by_key(|(value, _)| *value)
+        .ok_or_else(|| anyhow!("Did not get a maximum value"))
 }
 
 #[derive(Debug, PartialEq, Copy, Clone)]
@@ -44,24 +79,34 @@ enum PhaseSetting {
     Two,
     Three,
     Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
 }
 
 impl PhaseSetting {
-    fn value(self) -> i32 {
+    fn value(self) -> i64 {
         match self {
             PhaseSetting::Zero => 0,
             PhaseSetting::One => 1,
             PhaseSetting::Two => 2,
             PhaseSetting::Three => 3,
             PhaseSetting::Four => 4,
+            PhaseSetting::Five => 5,
+            PhaseSetting::Six => 6,
+            PhaseSetting::Seven => 7,
+            PhaseSetting::Eight => 8,
+            PhaseSetting::Nine => 9,
         }
     }
 }
 
 fn evaluate_sequence_for_program(
-    program: Vec<i32>,
+    program: Vec<i64>,
     phase_sequence: [PhaseSetting; 5],
-) -> Result<i32, anyhow::Error> {
+) -> Result<i64, anyhow::Error> {
     let mut transferred_output = 0;
 
     for phase_setting in phase_sequence.iter().copied().map(PhaseSetting::value) {
@@ -70,13 +115,55 @@ fn evaluate_sequence_for_program(
             VecDeque::from(vec![phase_setting, transferred_output]),
         );
         transferred_output = computer
-            .execute()?
+            .run_to_halt()?
             .pop_front()
             .ok_or_else(|| anyhow!("Did not get output from program execution"))?;
     }
     Ok(transferred_output)
 }
 
+/// Runs five persistent amplifiers in a ring, feeding each signal into the next amplifier until
+/// the last one halts, and returns the last signal that it sent to the thrusters
+fn evaluate_feedback_loop_for_program(
+    program: Vec<i64>,
+    phase_sequence: [PhaseSetting; 5],
+) -> Result<i64, anyhow::Error> {
+    let mut amplifiers: Vec<IntcodeComputer> = phase_sequence
+        .iter()
+        .map(|phase_setting| {
+            IntcodeComputer::new_with_input(
+                program.clone(),
+                VecDeque::from(vec![phase_setting.value()]),
+            )
+        })
+        .collect();
+    let last_amplifier = amplifiers.len() - 1;
+    let mut transferred_output = 0;
+    let mut thruster_signal = None;
+
+    loop {
+        for (index, amplifier) in amplifiers.iter_mut().enumerate() {
+            amplifier.push_input(transferred_output);
+            match amplifier.execute_until_output()? {
+                ExecutionState::ProducedOutput(output) => {
+                    transferred_output = output;
+                    if index == last_amplifier {
+                        thruster_signal = Some(output);
+                    }
+                }
+                ExecutionState::Halted if index == last_amplifier => {
+                    return thruster_signal
+                        .ok_or_else(|| anyhow!("Did not get output from the last amplifier"));
+                }
+                ExecutionState::Halted => {}
+                ExecutionState::AwaitingInput => {
+                    return Err(anyhow!("Amplifier {} requested more than one input", index));
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -135,6 +222,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_should_calculate_signals_for_feedback_loops() {
+        let program = vec![
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ];
+        let phase_sequence = [
+            PhaseSetting::Nine,
+            PhaseSetting::Eight,
+            PhaseSetting::Seven,
+            PhaseSetting::Six,
+            PhaseSetting::Five,
+        ];
+        assert_eq!(
+            139629729,
+            evaluate_feedback_loop_for_program(program.clone(), phase_sequence)
+                .expect("Failed to evaluate sequence")
+        );
+        assert_eq!(
+            (139629729, phase_sequence),
+            maximize_feedback_loop_output(program).expect("Failed to amplify output")
+        );
+
+        let program = vec![
+            3, 52, 1001, 52, -5, 52, 3, 53, 1, 52, 56, 54, 1007, 54, 5, 55, 1005, 55, 26, 1001, 54,
+            -5, 54, 1105, 1, 12, 1, 53, 54, 53, 1008, 54, 0, 55, 1001, 55, 1, 55, 2, 53, 55, 53, 4,
+            53, 1001, 56, -1, 56, 1005, 56, 6, 99, 0, 0, 0, 0, 10,
+        ];
+        let phase_sequence = [
+            PhaseSetting::Nine,
+            PhaseSetting::Seven,
+            PhaseSetting::Eight,
+            PhaseSetting::Five,
+            PhaseSetting::Six,
+        ];
+        assert_eq!(
+            (18216, phase_sequence),
+            maximize_feedback_loop_output(program).expect("Failed to amplify output")
+        );
+    }
+
     #[test]
     fn it_should_maximize_amplifier_output() {
         let program = vec![
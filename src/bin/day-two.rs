@@ -1,9 +1,11 @@
+use advent_of_code::program::Program;
+
 fn main() {
     let puzzle_input = include_str!("../../data/day-two-input.txt");
     let program: Vec<u32> = puzzle_input
-        .split(',')
-        .flat_map(|l| u32::from_str_radix(l, 10).into_iter())
-        .collect();
+        .parse::<Program<u32>>()
+        .expect("Invalid puzzle input")
+        .into_words();
 
     // reset program
     for i in 0..100 {
@@ -0,0 +1,18 @@
+use anyhow::{anyhow, Context};
+
+use advent_of_code::assembler::assemble;
+
+fn main() -> Result<(), anyhow::Error> {
+    let path = std::env::args()
+        .nth(1)
+        .ok_or_else(|| anyhow!("Usage: intcode-asm <source>"))?;
+
+    let source =
+        std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path))?;
+    let program: Vec<i64> =
+        assemble(&source).with_context(|| format!("Failed to assemble {}", path))?;
+
+    let words: Vec<String> = program.iter().map(i64::to_string).collect();
+    println!("{}", words.join(","));
+    Ok(())
+}
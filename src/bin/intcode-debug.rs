@@ -0,0 +1,405 @@
+use anyhow::{anyhow, Context};
+
+use std::io::{self, BufRead, Write};
+
+use advent_of_code::debugger::{Debugger, StopReason, DEFAULT_HISTORY};
+use advent_of_code::intcode_computer::{IntcodeComputer, Snapshot, SnapshotFormat};
+use advent_of_code::program::Program;
+
+const HELP: &str = "Commands:
+  s, step [count]          execute instructions, ignoring breakpoints
+  n, next                  execute until the instruction after the current one
+  c, continue              execute until a breakpoint, watchpoint, halt or missing input
+  rs, reverse-step [count] undo instructions, ignoring breakpoints
+  rc, reverse-continue     undo instructions until a breakpoint or watchpoint
+  rewind <step>            undo instructions until only that many have been executed
+  b, break <address>       break before the instruction at the address executes
+  b, break op <opcode>     break before any instruction with the opcode (a number or mnemonic)
+  w, watch <address>       break after any instruction writes to the address
+  d, delete <address>      remove the breakpoint and watchpoint on the address
+  d, delete op <opcode>    remove the breakpoint on the opcode
+  l, list                  list breakpoints and watchpoints
+  x <address> [count]      print memory cells
+  set <address> <value>    write a memory cell
+  i, input [values...]     print the input queue, or add values to it
+  i, input clear           empty the input queue
+  pc [address]             print or move the program counter
+  rb [value]               print or change the relative base
+  dis [address] [count]    disassemble instructions, starting at the program counter by default
+  save <path>              save a snapshot of the machine, in binary if the path ends in .bin
+  load <path>              replace the machine with a saved snapshot
+  h, help                  print this message
+  q, quit                  exit the debugger";
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Step(usize),
+    Next,
+    Continue,
+    ReverseStep(usize),
+    ReverseContinue,
+    Rewind(u64),
+    Break(usize),
+    BreakOpcode(i32),
+    Watch(usize),
+    Delete(usize),
+    DeleteOpcode(i32),
+    List,
+    Examine(usize, usize),
+    Set(usize, i64),
+    ShowInput,
+    AddInput(Vec<i64>),
+    ClearInput,
+    ProgramCounter(Option<usize>),
+    RelativeBase(Option<i64>),
+    Disassemble(Option<usize>, usize),
+    Save(String),
+    Load(String),
+    Help,
+    Quit,
+}
+
+fn parse_command(line: &str) -> Result<Command, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (name, arguments) = words
+        .split_first()
+        .ok_or_else(|| String::from("Empty command"))?;
+    let command = match (*name, arguments) {
+        ("s", []) | ("step", []) => Command::Step(1),
+        ("s", [count]) | ("step", [count]) => Command::Step(parse(count)?),
+        ("n", []) | ("next", []) => Command::Next,
+        ("c", []) | ("continue", []) => Command::Continue,
+        ("rs", []) | ("reverse-step", []) => Command::ReverseStep(1),
+        ("rs", [count]) | ("reverse-step", [count]) => Command::ReverseStep(parse(count)?),
+        ("rc", []) | ("reverse-continue", []) => Command::ReverseContinue,
+        ("rewind", [step]) => Command::Rewind(parse(step)?),
+        ("b", ["op", opcode]) | ("break", ["op", opcode]) => {
+            Command::BreakOpcode(parse_opcode(opcode)?)
+        }
+        ("b", [address]) | ("break", [address]) => Command::Break(parse(address)?),
+        ("w", [address]) | ("watch", [address]) => Command::Watch(parse(address)?),
+        ("d", ["op", opcode]) | ("delete", ["op", opcode]) => {
+            Command::DeleteOpcode(parse_opcode(opcode)?)
+        }
+        ("d", [address]) | ("delete", [address]) => Command::Delete(parse(address)?),
+        ("l", []) | ("list", []) => Command::List,
+        ("x", [address]) => Command::Examine(parse(address)?, 1),
+        ("x", [address, count]) => Command::Examine(parse(address)?, parse(count)?),
+        ("set", [address, value]) => Command::Set(parse(address)?, parse(value)?),
+        ("i", []) | ("input", []) => Command::ShowInput,
+        ("i", ["clear"]) | ("input", ["clear"]) => Command::ClearInput,
+        ("i", values) | ("input", values) => Command::AddInput(
+            values
+                .iter()
+                .map(|value| parse(value))
+                .collect::<Result<_, _>>()?,
+        ),
+        ("pc", []) => Command::ProgramCounter(None),
+        ("pc", [address]) => Command::ProgramCounter(Some(parse(address)?)),
+        ("rb", []) => Command::RelativeBase(None),
+        ("rb", [value]) => Command::RelativeBase(Some(parse(value)?)),
+        ("dis", []) => Command::Disassemble(None, 5),
+        ("dis", [address]) => Command::Disassemble(Some(parse(address)?), 5),
+        ("dis", [address, count]) => Command::Disassemble(Some(parse(address)?), parse(count)?),
+        ("save", [path]) => Command::Save(path.to_string()),
+        ("load", [path]) => Command::Load(path.to_string()),
+        ("h", []) | ("help", []) => Command::Help,
+        ("q", []) | ("quit", []) => Command::Quit,
+        _ => return Err(format!("Unknown command {:?}, try help", line.trim())),
+    };
+    Ok(command)
+}
+
+fn parse<T: std::str::FromStr>(word: &str) -> Result<T, String> {
+    word.parse()
+        .map_err(|_| format!("Unable to parse {:?}", word))
+}
+
+fn parse_opcode(word: &str) -> Result<i32, String> {
+    let opcode = match word.to_ascii_lowercase().as_str() {
+        "add" => 1,
+        "mul" => 2,
+        "in" => 3,
+        "out" => 4,
+        "jnz" => 5,
+        "jz" => 6,
+        "lt" => 7,
+        "eq" => 8,
+        "arb" => 9,
+        "hlt" => 99,
+        _ => parse(word)?,
+    };
+    Ok(opcode)
+}
+
+fn main() -> Result<(), anyhow::Error> {
+    let mut arguments = std::env::args().skip(1);
+    let path = arguments
+        .next()
+        .ok_or_else(|| anyhow!("Usage: intcode-debug <program> [input...]"))?;
+    let program: Vec<i64> = Program::from_file(&path)
+        .with_context(|| format!("Failed to load {}", path))?
+        .into_words();
+    let input = arguments
+        .map(|argument| argument.parse::<i64>())
+        .collect::<Result<_, _>>()
+        .context("Failed to parse input")?;
+
+    let mut debugger = Debugger::new(IntcodeComputer::new_with_input(program, input));
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    show_location(&debugger);
+    loop {
+        print!("(intcode) ");
+        io::stdout().flush()?;
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => return Ok(()),
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_command(&line) {
+            Ok(Command::Quit) => return Ok(()),
+            Ok(command) => perform_command(&mut debugger, command),
+            Err(message) => println!("{}", message),
+        }
+    }
+}
+
+fn perform_command(debugger: &mut Debugger, command: Command) {
+    match command {
+        Command::Step(count) => {
+            for _ in 0..count {
+                let result = debugger.step();
+                let stepped = matches!(result, Ok(StopReason::Stepped(_)));
+                report(debugger, result);
+                if !stepped {
+                    break;
+                }
+            }
+        }
+        Command::Next => {
+            let result = debugger.step_over();
+            report(debugger, result);
+        }
+        Command::Continue => {
+            let result = debugger.continue_execution();
+            report(debugger, result);
+        }
+        Command::ReverseStep(count) => {
+            for _ in 0..count {
+                let reason = debugger.step_back();
+                let stepped = reason == StopReason::SteppedBack;
+                report(debugger, Ok::<_, String>(reason));
+                if !stepped {
+                    break;
+                }
+            }
+        }
+        Command::ReverseContinue => {
+            let reason = debugger.reverse_continue();
+            report(debugger, Ok::<_, String>(reason));
+        }
+        Command::Rewind(step) => {
+            if debugger.rewind_to(step) {
+                show_location(debugger);
+            } else {
+                println!(
+                    "Unable to rewind to step {}, the history starts at step {}",
+                    step,
+                    debugger
+                        .computer()
+                        .earliest_step()
+                        .unwrap_or_else(|| debugger.computer().steps())
+                );
+            }
+        }
+        Command::Break(address) => {
+            debugger.add_breakpoint(address);
+            println!("Breakpoint at {}", address);
+        }
+        Command::BreakOpcode(opcode) => {
+            debugger.add_opcode_breakpoint(opcode);
+            println!("Breakpoint on opcode {}", opcode);
+        }
+        Command::Watch(address) => {
+            debugger.add_watchpoint(address);
+            println!("Watchpoint at {}", address);
+        }
+        Command::Delete(address) => {
+            let removed_breakpoint = debugger.remove_breakpoint(address);
+            let removed_watchpoint = debugger.remove_watchpoint(address);
+            if !removed_breakpoint && !removed_watchpoint {
+                println!("Nothing to delete at {}", address);
+            }
+        }
+        Command::DeleteOpcode(opcode) => {
+            if !debugger.remove_opcode_breakpoint(opcode) {
+                println!("No breakpoint on opcode {}", opcode);
+            }
+        }
+        Command::List => {
+            debugger
+                .breakpoints()
+                .iter()
+                .for_each(|address| println!("Breakpoint at {}", address));
+            debugger
+                .opcode_breakpoints()
+                .iter()
+                .for_each(|opcode| println!("Breakpoint on opcode {}", opcode));
+            debugger
+                .watchpoints()
+                .iter()
+                .for_each(|address| println!("Watchpoint at {}", address));
+        }
+        Command::Examine(address, count) => match address.checked_add(count) {
+            Some(end) => {
+                for cell in address..end {
+                    println!("{:>6}: {}", cell, debugger.computer().read_memory(cell));
+                }
+            }
+            None => println!("{} cells from {} run past the last address", count, address),
+        },
+        Command::Set(address, value) => debugger.computer_mut().write_memory(address, value),
+        Command::ShowInput => println!("Input: {:?}", debugger.computer().input_queue()),
+        Command::AddInput(values) => debugger.computer_mut().input_queue_mut().extend(values),
+        Command::ClearInput => debugger.computer_mut().input_queue_mut().clear(),
+        Command::ProgramCounter(Some(address)) => {
+            debugger.computer_mut().set_program_counter(address);
+            show_location(debugger);
+        }
+        Command::ProgramCounter(None) => show_location(debugger),
+        Command::RelativeBase(Some(value)) => debugger.computer_mut().set_relative_base(value),
+        Command::RelativeBase(None) => {
+            println!("Relative base: {}", debugger.computer().relative_base())
+        }
+        Command::Disassemble(address, count) => {
+            let mut address = address.unwrap_or_else(|| debugger.computer().program_counter());
+            for _ in 0..count {
+                match debugger.instruction_at(address) {
+                    Some(instruction) => {
+                        println!("{:>6}: {}", address, instruction);
+                        address = instruction.next_address();
+                    }
+                    None => {
+                        println!(
+                            "{:>6}: DATA {}",
+                            address,
+                            debugger.computer().read_memory(address)
+                        );
+                        match address.checked_add(1) {
+                            Some(next) => address = next,
+                            None => break,
+                        }
+                    }
+                }
+            }
+        }
+        Command::Save(path) => {
+            let format = if path.ends_with(".bin") {
+                SnapshotFormat::Binary
+            } else {
+                SnapshotFormat::Json
+            };
+            match debugger.computer().snapshot().save(&path, format) {
+                Ok(()) => println!("Saved a snapshot to {}", path),
+                Err(error) => println!("Error: {}", error),
+            }
+        }
+        Command::Load(path) => match Snapshot::load(&path) {
+            Ok(snapshot) => {
+                let mut computer = IntcodeComputer::from_snapshot(snapshot);
+                computer.enable_journal(DEFAULT_HISTORY);
+                *debugger.computer_mut() = computer;
+                show_location(debugger);
+            }
+            Err(error) => println!("Error: {}", error),
+        },
+        Command::Help => println!("{}", HELP),
+        Command::Quit => {}
+    }
+}
+
+fn report(debugger: &mut Debugger, result: Result<StopReason, impl std::fmt::Display>) {
+    for value in debugger.computer_mut().take_output() {
+        println!("Output: {}", value);
+    }
+    match result {
+        Ok(StopReason::Stepped(event)) => {
+            if let Some(write) = event.memory_write {
+                println!("[{}] {} -> {}", write.address, write.previous, write.value);
+            }
+        }
+        Ok(StopReason::SteppedOver) | Ok(StopReason::SteppedBack) => {}
+        Ok(StopReason::StartOfHistory) => println!("Reached the start of the history"),
+        Ok(StopReason::Breakpoint(address)) => println!("Breakpoint at {}", address),
+        Ok(StopReason::OpcodeBreakpoint { opcode, .. }) => {
+            println!("Breakpoint on opcode {}", opcode)
+        }
+        Ok(StopReason::Watchpoint(write)) => println!(
+            "Watchpoint [{}] {} -> {}",
+            write.address, write.previous, write.value
+        ),
+        Ok(StopReason::AwaitingInput) => println!("Waiting for input, add some with `input`"),
+        Ok(StopReason::Halted) => println!("Halted"),
+        Err(error) => println!("Error: {}", error),
+    }
+    show_location(debugger);
+}
+
+fn show_location(debugger: &Debugger) {
+    let computer = debugger.computer();
+    let program_counter = computer.program_counter();
+    let description = match debugger.instruction_at(program_counter) {
+        Some(instruction) => instruction.to_string(),
+        None => format!("DATA {}", computer.read_memory(program_counter)),
+    };
+    println!(
+        "=> {:>6}: {:<28} ; step {}",
+        program_counter,
+        description,
+        computer.steps()
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_should_parse_commands() {
+        assert_eq!(Ok(Command::Step(1)), parse_command("s"));
+        assert_eq!(Ok(Command::Step(10)), parse_command("step 10"));
+        assert_eq!(Ok(Command::Break(225)), parse_command("b 225"));
+        assert_eq!(Ok(Command::BreakOpcode(4)), parse_command("break op OUT"));
+        assert_eq!(Ok(Command::DeleteOpcode(99)), parse_command("d op 99"));
+        assert_eq!(Ok(Command::Examine(10, 4)), parse_command("x 10 4"));
+        assert_eq!(Ok(Command::Set(10, -4)), parse_command("set 10 -4"));
+        assert_eq!(
+            Ok(Command::AddInput(vec![1, 5])),
+            parse_command("input 1 5")
+        );
+        assert_eq!(Ok(Command::ClearInput), parse_command("i clear"));
+        assert_eq!(Ok(Command::ReverseStep(3)), parse_command("rs 3"));
+        assert_eq!(Ok(Command::Rewind(120)), parse_command("rewind 120"));
+        assert_eq!(
+            Ok(Command::Save(String::from("state.bin"))),
+            parse_command("save state.bin")
+        );
+        assert_eq!(Ok(Command::ProgramCounter(None)), parse_command("  pc "));
+        assert_eq!(Ok(Command::Disassemble(Some(6), 5)), parse_command("dis 6"));
+    }
+
+    #[test]
+    fn it_should_reject_invalid_commands() {
+        assert_eq!(
+            Err(String::from("Unable to parse \"ten\"")),
+            parse_command("step ten")
+        );
+        assert_eq!(
+            Err(String::from("Unknown command \"jump 4\", try help")),
+            parse_command("jump 4")
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use anyhow::{anyhow, Context};
+
+use advent_of_code::control_flow::ControlFlowGraph;
+use advent_of_code::decompiler::decompile;
+use advent_of_code::disassembler::{disassemble, DisassemblerOptions};
+use advent_of_code::program::Program;
+
+const USAGE: &str = "Usage: intcode-disassemble [--follow-jumps] [--mark-boundaries] [--cfg | --decompile] [--entry <address>]... <program>";
+
+fn main() -> Result<(), anyhow::Error> {
+    let mut options = DisassemblerOptions::default();
+    let mut graph = false;
+    let mut pseudocode = false;
+    let mut entry_points = vec![0];
+    let mut path = None;
+    let mut arguments = std::env::args().skip(1);
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--follow-jumps" => options.follow_jumps = true,
+            "--mark-boundaries" => options.mark_boundaries = true,
+            "--cfg" => graph = true,
+            "--decompile" => pseudocode = true,
+            "--entry" => entry_points.push(
+                arguments
+                    .next()
+                    .ok_or_else(|| anyhow!(USAGE))?
+                    .parse()
+                    .context("Failed to parse the entry point")?,
+            ),
+            _ => path = Some(argument),
+        }
+    }
+    if graph && pseudocode {
+        return Err(anyhow!(USAGE));
+    }
+    let path = path.ok_or_else(|| anyhow!(USAGE))?;
+
+    let program: Vec<i64> = Program::from_file(&path)
+        .with_context(|| format!("Failed to load {}", path))?
+        .into_words();
+
+    if graph {
+        print!(
+            "{}",
+            ControlFlowGraph::with_entry_points(&program, &entry_points).to_dot()
+        );
+    } else if pseudocode {
+        print!("{}", decompile(&program, &entry_points));
+    } else {
+        options.entry_points = entry_points;
+        print!("{}", disassemble(&program, &options));
+    }
+    Ok(())
+}
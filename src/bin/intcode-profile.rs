@@ -0,0 +1,48 @@
+use anyhow::{anyhow, Context};
+
+use advent_of_code::intcode_computer::{ExecutionState, IntcodeComputer};
+use advent_of_code::program::Program;
+
+const USAGE: &str = "Usage: intcode-profile [--top <count>] <program> [input...]";
+
+fn main() -> Result<(), anyhow::Error> {
+    let mut top = 20;
+    let mut arguments = std::env::args().skip(1).peekable();
+    if arguments.peek().map(String::as_str) == Some("--top") {
+        arguments.next();
+        top = arguments
+            .next()
+            .ok_or_else(|| anyhow!(USAGE))?
+            .parse()
+            .context("Failed to parse the number of addresses")?;
+    }
+    let path = arguments.next().ok_or_else(|| anyhow!(USAGE))?;
+    let program: Vec<i64> = Program::from_file(&path)
+        .with_context(|| format!("Failed to load {}", path))?
+        .into_words();
+    let input = arguments
+        .map(|argument| argument.parse::<i64>())
+        .collect::<Result<_, _>>()
+        .context("Failed to parse input")?;
+
+    let mut computer = IntcodeComputer::new_with_input(program, input);
+    computer.enable_profiling();
+    let state = computer.execute()?;
+    let output: Vec<String> = computer
+        .take_output()
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    println!("Output: {}", output.join(","));
+    if state == ExecutionState::AwaitingInput {
+        println!(
+            "Stopped at {} waiting for input",
+            computer.program_counter()
+        );
+    }
+    println!();
+    if let Some(report) = computer.profile_report(top) {
+        print!("{}", report);
+    }
+    Ok(())
+}
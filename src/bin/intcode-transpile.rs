@@ -0,0 +1,25 @@
+use anyhow::{anyhow, Context};
+
+use advent_of_code::program::Program;
+use advent_of_code::transpiler::{transpile, Language};
+
+const USAGE: &str = "Usage: intcode-transpile [--lang c|rust] <program>";
+
+fn main() -> Result<(), anyhow::Error> {
+    let mut language = Language::C;
+    let mut arguments = std::env::args().skip(1).peekable();
+    if arguments.peek().map(String::as_str) == Some("--lang") {
+        arguments.next();
+        language = arguments
+            .next()
+            .ok_or_else(|| anyhow!(USAGE))?
+            .parse()
+            .map_err(|error: String| anyhow!(error))?;
+    }
+    let path = arguments.next().ok_or_else(|| anyhow!(USAGE))?;
+    let program: Vec<i64> = Program::from_file(&path)
+        .with_context(|| format!("Failed to load {}", path))?
+        .into_words();
+    print!("{}", transpile(&program, language));
+    Ok(())
+}
@@ -0,0 +1,310 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use crate::disassembler::{reachable_instructions, Instruction};
+use crate::intcode_computer::{Operation, ParameterMode, Word};
+
+/// Where execution can go after the last instruction of a basic block
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Successor {
+    /// Continues with the instruction directly after the block
+    FallThrough(usize),
+    /// Jumps to an immediate target
+    Jump(usize),
+    /// Jumps to a target that is read from memory, so it can not be known without running the
+    /// program
+    IndirectJump,
+    Halt,
+}
+
+/// A run of instructions that is only ever entered at its first instruction and left after its
+/// last one
+#[derive(Debug, PartialEq, Clone)]
+pub struct BasicBlock<W: Word = i64> {
+    pub start: usize,
+    pub instructions: Vec<Instruction<W>>,
+    pub successors: Vec<Successor>,
+}
+
+impl<W: Word> BasicBlock<W> {
+    /// The address directly after the last instruction of the block
+    pub fn end(&self) -> usize {
+        self.instructions
+            .last()
+            .map_or(self.start, Instruction::next_address)
+    }
+}
+
+/// The basic blocks of a program image, recovered without running it. Only code that can be
+/// reached from the entry points by falling through or following jumps with immediate targets
+/// is included, so code that the program patches before running it will be missing or wrong;
+/// building the graph from a snapshot of memory taken after the patch avoids that.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ControlFlowGraph<W: Word = i64> {
+    pub blocks: BTreeMap<usize, BasicBlock<W>>,
+}
+
+impl<W: Word> ControlFlowGraph<W> {
+    pub fn new(program: &[W]) -> Self {
+        Self::with_entry_points(program, &[0])
+    }
+
+    pub fn with_entry_points(program: &[W], entry_points: &[usize]) -> Self {
+        let instructions: Vec<Instruction<W>> = reachable_instructions(program, entry_points)
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let mut leaders: BTreeSet<usize> = entry_points.iter().copied().collect();
+        for instruction in instructions
+            .iter()
+            .filter(|instruction| instruction.is_jump())
+        {
+            leaders.insert(instruction.next_address());
+            if instruction.can_jump() {
+                leaders.extend(instruction.jump_target());
+            }
+        }
+
+        let mut blocks: BTreeMap<usize, BasicBlock<W>> = BTreeMap::new();
+        let mut current: Option<BasicBlock<W>> = None;
+        for instruction in instructions {
+            let continues = current.as_ref().is_some_and(|block| {
+                block.end() == instruction.address && !leaders.contains(&instruction.address)
+            });
+            if !continues {
+                if let Some(block) = current.take() {
+                    blocks.insert(block.start, block);
+                }
+                current = Some(BasicBlock {
+                    start: instruction.address,
+                    instructions: Vec::new(),
+                    successors: Vec::new(),
+                });
+            }
+            let block = current.as_mut().expect("A block was just started");
+            let ends_block = instruction.is_jump() || instruction.operation == Operation::Terminate;
+            block.instructions.push(instruction);
+            if ends_block {
+                blocks.insert(block.start, current.take().expect("The block exists"));
+            }
+        }
+        if let Some(block) = current {
+            blocks.insert(block.start, block);
+        }
+
+        for block in blocks.values_mut() {
+            block.successors = successors(block.instructions.last());
+        }
+        ControlFlowGraph { blocks }
+    }
+
+    /// The addresses of the jumps whose targets could not be resolved
+    pub fn indirect_jumps(&self) -> Vec<usize> {
+        self.blocks
+            .values()
+            .filter(|block| block.successors.contains(&Successor::IndirectJump))
+            .filter_map(|block| block.instructions.last())
+            .map(|instruction| instruction.address)
+            .collect()
+    }
+
+    /// Renders the graph in the Graphviz DOT language. Indirect jumps lead to dashed nodes, and
+    /// edges to addresses where no instruction could be decoded lead to red ones.
+    pub fn to_dot(&self) -> String {
+        let mut dot =
+            String::from("digraph cfg {\n    node [shape=box, fontname=\"monospace\"];\n");
+        let mut edges = String::new();
+        let mut invalid = BTreeSet::new();
+        let mut halts = false;
+
+        for block in self.blocks.values() {
+            let label: String = block
+                .instructions
+                .iter()
+                .map(|instruction| format!("{}: {}\\l", instruction.address, instruction))
+                .collect();
+            writeln!(
+                dot,
+                "    block_{} [label=\"{}\"];",
+                block.start,
+                escape(&label)
+            )
+            .expect("Failed to write to a string");
+
+            for successor in &block.successors {
+                let (target, attributes) = match successor {
+                    Successor::FallThrough(address) | Successor::Jump(address) => {
+                        let node = if self.blocks.contains_key(address) {
+                            format!("block_{}", address)
+                        } else {
+                            invalid.insert(*address);
+                            format!("invalid_{}", address)
+                        };
+                        match successor {
+                            Successor::Jump(_) => (node, " [label=\"jump\"]"),
+                            _ => (node, ""),
+                        }
+                    }
+                    Successor::IndirectJump => {
+                        writeln!(
+                            dot,
+                            "    indirect_{} [label=\"indirect jump\", shape=diamond, style=dashed];",
+                            block.start
+                        )
+                        .expect("Failed to write to a string");
+                        (
+                            format!("indirect_{}", block.start),
+                            " [label=\"jump\", style=dashed]",
+                        )
+                    }
+                    Successor::Halt => {
+                        halts = true;
+                        ("halt".to_string(), "")
+                    }
+                };
+                writeln!(
+                    edges,
+                    "    block_{} -> {}{};",
+                    block.start, target, attributes
+                )
+                .expect("Failed to write to a string");
+            }
+        }
+
+        if halts {
+            dot.push_str("    halt [label=\"halt\", shape=doublecircle];\n");
+        }
+        for address in invalid {
+            writeln!(
+                dot,
+                "    invalid_{} [label=\"invalid {}\", color=red];",
+                address, address
+            )
+            .expect("Failed to write to a string");
+        }
+        dot.push_str(&edges);
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn successors<W: Word>(last: Option<&Instruction<W>>) -> Vec<Successor> {
+    let instruction = match last {
+        Some(instruction) => instruction,
+        None => return Vec::new(),
+    };
+    if instruction.operation == Operation::Terminate {
+        return vec![Successor::Halt];
+    }
+    let mut successors = Vec::new();
+    if instruction.falls_through() {
+        successors.push(Successor::FallThrough(instruction.next_address()));
+    }
+    if instruction.can_jump() {
+        successors.push(match instruction.operation {
+            Operation::JumpIfTrue(_, ParameterMode::Immediate)
+            | Operation::JumpIfFalse(_, ParameterMode::Immediate) => instruction
+                .jump_target()
+                .map_or(Successor::IndirectJump, Successor::Jump),
+            _ => Successor::IndirectJump,
+        });
+    }
+    successors
+}
+
+fn escape(label: &str) -> String {
+    label.replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::assembler::assemble;
+    use crate::intcode_computer::IntcodeComputer;
+    use crate::program::Program;
+
+    // counts down from its input, then jumps to wherever the counter points
+    const COUNTDOWN: &str = "
+                IN   [n]
+        loop:   JZ   [n], #done
+                ADD  [n], #-1, [n]
+                OUT  [n]
+                JNZ  #1, #loop
+        done:   JNZ  [n], [n]
+                HLT
+        n:      .data 0
+    ";
+
+    #[test]
+    fn it_should_recover_basic_blocks() {
+        let program: Vec<i64> = assemble(COUNTDOWN).expect("Failed to assemble");
+        let graph = ControlFlowGraph::new(&program);
+        assert_eq!(
+            vec![
+                (0, 2, vec![Successor::FallThrough(2)]),
+                (2, 5, vec![Successor::FallThrough(5), Successor::Jump(14)]),
+                (5, 14, vec![Successor::Jump(2)]),
+                (
+                    14,
+                    17,
+                    vec![Successor::FallThrough(17), Successor::IndirectJump]
+                ),
+                (17, 18, vec![Successor::Halt]),
+            ],
+            graph
+                .blocks
+                .values()
+                .map(|block| (block.start, block.end(), block.successors.clone()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(3, graph.blocks[&5].instructions.len());
+        assert_eq!(vec![14], graph.indirect_jumps());
+    }
+
+    #[test]
+    fn it_should_export_graphviz() {
+        let program: Vec<i64> = vec![1005, 9, 50, 6, 9, 8, 99, 0, 0, 0];
+        let graph = ControlFlowGraph::new(&program);
+        assert_eq!(
+            "digraph cfg {
+    node [shape=box, fontname=\"monospace\"];
+    block_0 [label=\"0: JNZ  [9], #50\\l\"];
+    block_3 [label=\"3: JZ   [9], [8]\\l\"];
+    indirect_3 [label=\"indirect jump\", shape=diamond, style=dashed];
+    block_6 [label=\"6: HLT\\l\"];
+    halt [label=\"halt\", shape=doublecircle];
+    invalid_50 [label=\"invalid 50\", color=red];
+    block_0 -> block_3;
+    block_0 -> invalid_50 [label=\"jump\"];
+    block_3 -> block_6;
+    block_3 -> indirect_3 [label=\"jump\", style=dashed];
+    block_6 -> halt;
+}
+",
+            graph.to_dot()
+        );
+    }
+
+    #[test]
+    fn it_should_graph_the_day_five_program() {
+        let program: Vec<i64> = Program::from_file("data/day-five-input.txt")
+            .expect("Failed to load")
+            .into_words();
+        // the second instruction patches the third with the input before it runs
+        let mut computer = IntcodeComputer::new(program);
+        computer.push_input(5);
+        computer.step().expect("Failed to step");
+        computer.step().expect("Failed to step");
+        let image = computer.snapshot().image;
+
+        let graph = ControlFlowGraph::new(&image);
+        assert_eq!(vec![Successor::Jump(238)], graph.blocks[&0].successors);
+        // the diagnostics finish with a jump to an address that the program stored in memory
+        assert_eq!(vec![288], graph.indirect_jumps());
+        assert!(graph
+            .to_dot()
+            .contains("block_0 -> block_238 [label=\"jump\"];\n"));
+    }
+}
@@ -0,0 +1,411 @@
+use std::collections::BTreeSet;
+
+use crate::disassembler::Instruction;
+use crate::intcode_computer::{
+    ExecutionError, ExecutionEvent, IntcodeComputer, MemoryWrite, Operation, StepResult, Word,
+};
+
+/// Why the debugger handed control back to the user
+#[derive(Debug, PartialEq)]
+pub enum StopReason<W: Word = i64> {
+    /// A single instruction was executed by `step`
+    Stepped(ExecutionEvent<W>),
+    /// `step_over` reached the instruction after the one it started on
+    SteppedOver,
+    /// A single instruction was undone by `step_back`
+    SteppedBack,
+    Breakpoint(usize),
+    OpcodeBreakpoint {
+        address: usize,
+        opcode: i32,
+    },
+    Watchpoint(MemoryWrite<W>),
+    AwaitingInput,
+    Halted,
+    /// Reverse execution reached the oldest instruction in the journal
+    StartOfHistory,
+}
+
+/// The number of instructions that the debugger can step back through by default
+pub const DEFAULT_HISTORY: usize = 1_000_000;
+
+/// Controls an `IntcodeComputer` one instruction at a time, stopping at breakpoints on addresses
+/// and opcodes and at watchpoints on memory cells, in either direction
+pub struct Debugger<W: Word = i64> {
+    computer: IntcodeComputer<W>,
+    breakpoints: BTreeSet<usize>,
+    opcode_breakpoints: BTreeSet<i32>,
+    watchpoints: BTreeSet<usize>,
+}
+
+impl<W: Word> Debugger<W> {
+    pub fn new(computer: IntcodeComputer<W>) -> Self {
+        Self::with_history(computer, DEFAULT_HISTORY)
+    }
+
+    /// Creates a debugger that can step back through up to `history` instructions
+    pub fn with_history(mut computer: IntcodeComputer<W>, history: usize) -> Self {
+        computer.enable_journal(history);
+        Debugger {
+            computer,
+            breakpoints: BTreeSet::new(),
+            opcode_breakpoints: BTreeSet::new(),
+            watchpoints: BTreeSet::new(),
+        }
+    }
+
+    pub fn computer(&self) -> &IntcodeComputer<W> {
+        &self.computer
+    }
+
+    pub fn computer_mut(&mut self) -> &mut IntcodeComputer<W> {
+        &mut self.computer
+    }
+
+    pub fn breakpoints(&self) -> &BTreeSet<usize> {
+        &self.breakpoints
+    }
+
+    pub fn opcode_breakpoints(&self) -> &BTreeSet<i32> {
+        &self.opcode_breakpoints
+    }
+
+    pub fn watchpoints(&self) -> &BTreeSet<usize> {
+        &self.watchpoints
+    }
+
+    /// Returns whether the breakpoint was newly added
+    pub fn add_breakpoint(&mut self, address: usize) -> bool {
+        self.breakpoints.insert(address)
+    }
+
+    pub fn remove_breakpoint(&mut self, address: usize) -> bool {
+        self.breakpoints.remove(&address)
+    }
+
+    /// Breaks before any instruction with the opcode (the last two digits of its code) executes
+    pub fn add_opcode_breakpoint(&mut self, opcode: i32) -> bool {
+        self.opcode_breakpoints.insert(opcode)
+    }
+
+    pub fn remove_opcode_breakpoint(&mut self, opcode: i32) -> bool {
+        self.opcode_breakpoints.remove(&opcode)
+    }
+
+    /// Breaks after any instruction writes to the memory cell
+    pub fn add_watchpoint(&mut self, address: usize) -> bool {
+        self.watchpoints.insert(address)
+    }
+
+    pub fn remove_watchpoint(&mut self, address: usize) -> bool {
+        self.watchpoints.remove(&address)
+    }
+
+    /// Decodes the instruction at the address from the current state of memory, unless it would
+    /// run up against the last address
+    pub fn instruction_at(&self, address: usize) -> Option<Instruction<W>> {
+        let words: Vec<W> = (0..4)
+            .map_while(|offset| address.checked_add(offset))
+            .map(|cell| self.computer.read_memory(cell).clone())
+            .collect();
+        Instruction::decode(&words, 0)
+            .filter(|instruction| address.checked_add(instruction.next_address()).is_some())
+            .map(|instruction| Instruction {
+                address,
+                ..instruction
+            })
+    }
+
+    /// Executes exactly one instruction, ignoring breakpoints and watchpoints
+    pub fn step(&mut self) -> Result<StopReason<W>, ExecutionError<W>> {
+        Ok(match self.computer.step()? {
+            StepResult::Executed(event) => StopReason::Stepped(event),
+            StepResult::AwaitingInput => StopReason::AwaitingInput,
+            StepResult::Halted => StopReason::Halted,
+        })
+    }
+
+    /// Executes until control reaches the instruction directly after the current one, which runs
+    /// any loop or subroutine that the current instruction jumps into as a single step
+    pub fn step_over(&mut self) -> Result<StopReason<W>, ExecutionError<W>> {
+        let program_counter = self.computer.program_counter();
+        let return_address = self
+            .instruction_at(program_counter)
+            .map(|instruction| instruction.next_address())
+            .ok_or_else(|| ExecutionError::InvalidOperationCode {
+                index: program_counter,
+                code: self.computer.read_memory(program_counter).clone(),
+            })?;
+        self.run(Some(return_address))
+    }
+
+    /// Executes until a breakpoint or watchpoint is hit, the program halts, or it requests input
+    /// that is not available. The instruction at the program counter always executes, so that
+    /// continuing from a breakpoint does not stop at it again immediately.
+    pub fn continue_execution(&mut self) -> Result<StopReason<W>, ExecutionError<W>> {
+        self.run(None)
+    }
+
+    /// Undoes the most recently executed instruction, ignoring breakpoints and watchpoints
+    pub fn step_back(&mut self) -> StopReason<W> {
+        match self.computer.step_back() {
+            Some(_) => StopReason::SteppedBack,
+            None => StopReason::StartOfHistory,
+        }
+    }
+
+    /// Undoes instructions until a breakpoint is reached, a write to a watched memory cell is
+    /// undone, or the journal runs out. The most recent instruction is always undone, so that
+    /// reversing from a breakpoint does not stop at it again immediately.
+    pub fn reverse_continue(&mut self) -> StopReason<W> {
+        loop {
+            let entry = match self.computer.step_back() {
+                Some(entry) => entry,
+                None => return StopReason::StartOfHistory,
+            };
+            match entry.memory_write {
+                Some(write) if self.watchpoints.contains(&write.address) => {
+                    return StopReason::Watchpoint(write);
+                }
+                _ => {}
+            }
+            if let Some(reason) = self.check_breakpoints(entry.address) {
+                return reason;
+            }
+        }
+    }
+
+    /// Moves back to the point where `step` instructions had been executed, returning false if
+    /// the history does not reach back that far
+    pub fn rewind_to(&mut self, step: u64) -> bool {
+        self.computer.rewind_to(step)
+    }
+
+    fn run(&mut self, return_address: Option<usize>) -> Result<StopReason<W>, ExecutionError<W>> {
+        let mut first = true;
+        loop {
+            let program_counter = self.computer.program_counter();
+            if !first {
+                if Some(program_counter) == return_address {
+                    return Ok(StopReason::SteppedOver);
+                }
+                if let Some(reason) = self.check_breakpoints(program_counter) {
+                    return Ok(reason);
+                }
+            }
+            first = false;
+
+            match self.computer.step()? {
+                StepResult::Executed(ExecutionEvent {
+                    memory_write: Some(write),
+                    ..
+                }) if self.watchpoints.contains(&write.address) => {
+                    return Ok(StopReason::Watchpoint(write));
+                }
+                StepResult::Executed(_) => {}
+                StepResult::AwaitingInput => return Ok(StopReason::AwaitingInput),
+                StepResult::Halted => return Ok(StopReason::Halted),
+            }
+        }
+    }
+
+    fn check_breakpoints(&self, address: usize) -> Option<StopReason<W>> {
+        if self.breakpoints.contains(&address) {
+            return Some(StopReason::Breakpoint(address));
+        }
+        let opcode = Operation::decode(self.computer.read_memory(address))
+            .ok()
+            .map(|operation| operation.encode() % 100)?;
+        if self.opcode_breakpoints.contains(&opcode) {
+            Some(StopReason::OpcodeBreakpoint { address, opcode })
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::VecDeque;
+
+    // counts the cell at address 20 up from zero to three, printing it after every increment
+    const COUNTER: [i64; 14] = [1001, 20, 1, 20, 4, 20, 1007, 20, 3, 21, 1005, 21, 0, 99];
+
+    fn setup_debugger() -> Debugger {
+        Debugger::new(IntcodeComputer::new(COUNTER.to_vec()))
+    }
+
+    #[test]
+    fn it_should_step_one_instruction_at_a_time() {
+        let mut debugger = setup_debugger();
+        debugger.add_breakpoint(4);
+        match debugger.step() {
+            Ok(StopReason::Stepped(event)) => {
+                assert_eq!(0, event.address);
+                assert_eq!(4, event.program_counter);
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+        assert!(matches!(debugger.step(), Ok(StopReason::Stepped(_))));
+        assert_eq!(6, debugger.computer().program_counter());
+    }
+
+    #[test]
+    fn it_should_not_decode_past_the_last_address() {
+        let mut debugger = setup_debugger();
+        debugger.computer_mut().write_memory(usize::MAX - 1, 104);
+        debugger.computer_mut().write_memory(usize::MAX, 99);
+        assert_eq!(None, debugger.instruction_at(usize::MAX - 1));
+        assert_eq!(None, debugger.instruction_at(usize::MAX));
+        debugger.computer_mut().write_memory(usize::MAX - 2, 104);
+        assert_eq!(
+            Some(usize::MAX),
+            debugger
+                .instruction_at(usize::MAX - 2)
+                .map(|instruction| instruction.next_address())
+        );
+    }
+
+    #[test]
+    fn it_should_stop_at_breakpoints() {
+        let mut debugger = setup_debugger();
+        debugger.add_breakpoint(4);
+        assert_eq!(Ok(StopReason::Breakpoint(4)), debugger.continue_execution());
+        assert_eq!(Ok(StopReason::Breakpoint(4)), debugger.continue_execution());
+        assert_eq!(
+            VecDeque::from(vec![1]),
+            debugger.computer_mut().take_output()
+        );
+
+        debugger.remove_breakpoint(4);
+        assert_eq!(Ok(StopReason::Halted), debugger.continue_execution());
+        assert_eq!(
+            VecDeque::from(vec![2, 3]),
+            debugger.computer_mut().take_output()
+        );
+    }
+
+    #[test]
+    fn it_should_stop_at_opcode_breakpoints() {
+        let mut debugger = setup_debugger();
+        debugger.add_opcode_breakpoint(5);
+        assert_eq!(
+            Ok(StopReason::OpcodeBreakpoint {
+                address: 10,
+                opcode: 5
+            }),
+            debugger.continue_execution()
+        );
+        debugger.add_opcode_breakpoint(99);
+        debugger.remove_opcode_breakpoint(5);
+        assert_eq!(
+            Ok(StopReason::OpcodeBreakpoint {
+                address: 13,
+                opcode: 99
+            }),
+            debugger.continue_execution()
+        );
+    }
+
+    #[test]
+    fn it_should_stop_at_watchpoints() {
+        let mut debugger = setup_debugger();
+        debugger.add_watchpoint(21);
+        assert_eq!(
+            Ok(StopReason::Watchpoint(MemoryWrite {
+                address: 21,
+                previous: 0,
+                value: 1
+            })),
+            debugger.continue_execution()
+        );
+        assert_eq!(10, debugger.computer().program_counter());
+    }
+
+    #[test]
+    fn it_should_step_over_loops() {
+        let mut debugger = setup_debugger();
+        debugger.computer_mut().set_program_counter(10);
+        debugger.computer_mut().write_memory(21, 1);
+        assert_eq!(Ok(StopReason::SteppedOver), debugger.step_over());
+        assert_eq!(13, debugger.computer().program_counter());
+        assert_eq!(
+            VecDeque::from(vec![1, 2, 3]),
+            debugger.computer_mut().take_output()
+        );
+
+        let mut debugger = setup_debugger();
+        assert_eq!(Ok(StopReason::SteppedOver), debugger.step_over());
+        assert_eq!(4, debugger.computer().program_counter());
+    }
+
+    #[test]
+    fn it_should_reverse_to_breakpoints_and_watchpoints() {
+        let mut debugger = setup_debugger();
+        assert_eq!(Ok(StopReason::Halted), debugger.continue_execution());
+        assert_eq!(
+            VecDeque::from(vec![1, 2, 3]),
+            debugger.computer_mut().take_output()
+        );
+
+        debugger.add_breakpoint(4);
+        assert_eq!(StopReason::Breakpoint(4), debugger.reverse_continue());
+        assert_eq!(&3, debugger.computer().read_memory(20));
+        assert_eq!(StopReason::Breakpoint(4), debugger.reverse_continue());
+        assert_eq!(&2, debugger.computer().read_memory(20));
+
+        debugger.remove_breakpoint(4);
+        debugger.add_watchpoint(20);
+        assert_eq!(
+            StopReason::Watchpoint(MemoryWrite {
+                address: 20,
+                previous: 1,
+                value: 2
+            }),
+            debugger.reverse_continue()
+        );
+        assert_eq!(&1, debugger.computer().read_memory(20));
+        assert_eq!(0, debugger.computer().program_counter());
+
+        assert_eq!(StopReason::SteppedBack, debugger.step_back());
+        assert_eq!(10, debugger.computer().program_counter());
+        debugger.remove_watchpoint(20);
+        assert_eq!(StopReason::StartOfHistory, debugger.reverse_continue());
+        assert_eq!(0, debugger.computer().steps());
+
+        assert!(debugger.rewind_to(0));
+        assert!(!debugger.rewind_to(3));
+        debugger.add_watchpoint(20);
+        assert_eq!(
+            Ok(StopReason::Watchpoint(MemoryWrite {
+                address: 20,
+                previous: 0,
+                value: 1
+            })),
+            debugger.continue_execution()
+        );
+    }
+
+    #[test]
+    fn it_should_decode_instructions_from_memory() {
+        let mut debugger = setup_debugger();
+        assert_eq!(
+            "ADD  [20], #1, [20]",
+            debugger
+                .instruction_at(0)
+                .expect("Failed to decode instruction")
+                .to_string()
+        );
+        debugger.computer_mut().write_memory(0, 1);
+        assert_eq!(
+            "ADD  [20], [1], [20]",
+            debugger
+                .instruction_at(0)
+                .expect("Failed to decode instruction")
+                .to_string()
+        );
+        assert_eq!(None, debugger.instruction_at(1));
+    }
+}
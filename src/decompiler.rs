@@ -0,0 +1,883 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::control_flow::{BasicBlock, ControlFlowGraph, Successor};
+use crate::disassembler::Instruction;
+use crate::intcode_computer::{Operation, ParameterMode, Word};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum BinaryOperator {
+    Add,
+    Multiply,
+    LessThan,
+    EqualTo,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expression<W: Word = i64> {
+    Constant(W),
+    /// A memory cell that is only ever used as a variable, named after its address
+    Variable(usize),
+    /// A memory cell that also holds code
+    Cell(usize),
+    /// A memory cell whose address is only known at run time
+    Memory(Box<Expression<W>>),
+    /// The memory cell at an offset from the relative base
+    Relative(Box<Expression<W>>),
+    Read,
+    Binary(Box<Expression<W>>, BinaryOperator, Box<Expression<W>>),
+    Not(Box<Expression<W>>),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Statement<W: Word = i64> {
+    Assign(Expression<W>, Expression<W>),
+    Print(Expression<W>),
+    AdjustBase(Expression<W>),
+    Halt,
+    /// Continues at an address that is only known at run time, or that holds no instruction
+    Jump(Expression<W>),
+    Goto(usize),
+    Label(usize),
+    Break,
+    Continue,
+    If {
+        condition: Expression<W>,
+        then: Vec<Statement<W>>,
+        otherwise: Vec<Statement<W>>,
+    },
+    Loop(Vec<Statement<W>>),
+    While {
+        condition: Expression<W>,
+        body: Vec<Statement<W>>,
+    },
+    DoWhile {
+        body: Vec<Statement<W>>,
+        condition: Expression<W>,
+    },
+}
+
+/// The code that can be reached from one entry point
+#[derive(Debug, PartialEq, Clone)]
+pub struct Function<W: Word = i64> {
+    pub entry: usize,
+    pub body: Vec<Statement<W>>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Decompilation<W: Word = i64> {
+    /// The cells that are only used as variables, with their initial values
+    pub variables: BTreeMap<usize, W>,
+    pub functions: Vec<Function<W>>,
+}
+
+/// Lifts the code reachable from each entry point into structured pseudocode. Loops are
+/// recovered from backward jumps and `if`/`else` from forward ones, falling back to `goto`
+/// wherever the jumps do not nest. Parameters that the program overwrites are shown as reads of
+/// the cells that hold them, since their values in the image are not the ones that will run.
+pub fn decompile<W: Word>(program: &[W], entry_points: &[usize]) -> Decompilation<W> {
+    let graph = ControlFlowGraph::with_entry_points(program, entry_points);
+    let lifter = Lifter::new(program, &graph);
+
+    let functions = entry_points
+        .iter()
+        .map(|&entry| {
+            let mut structurer = Structurer::new(&lifter, reachable_blocks(&graph, entry));
+            // the first pass finds the gotos that need labels for the second one
+            structurer.structure();
+            structurer.labels = std::mem::take(&mut structurer.gotos);
+            Function {
+                entry,
+                body: structurer.structure(),
+            }
+        })
+        .collect();
+
+    let variables = lifter
+        .variables
+        .iter()
+        .map(|&address| {
+            let value = program.get(address).cloned().unwrap_or_else(W::zero);
+            (address, value)
+        })
+        .collect();
+    Decompilation {
+        variables,
+        functions,
+    }
+}
+
+fn reachable_blocks<W: Word>(graph: &ControlFlowGraph<W>, entry: usize) -> Vec<&BasicBlock<W>> {
+    let mut reached = BTreeSet::new();
+    let mut pending = vec![entry];
+    while let Some(address) = pending.pop() {
+        if let Some(block) = graph.blocks.get(&address) {
+            if reached.insert(address) {
+                pending.extend(
+                    block
+                        .successors
+                        .iter()
+                        .filter_map(|successor| match successor {
+                            Successor::FallThrough(next) | Successor::Jump(next) => Some(*next),
+                            _ => None,
+                        }),
+                );
+            }
+        }
+    }
+    reached
+        .iter()
+        .map(|address| &graph.blocks[address])
+        .collect()
+}
+
+/// Turns single instructions into statements and expressions
+struct Lifter<W: Word> {
+    code: BTreeSet<usize>,
+    written: BTreeSet<usize>,
+    variables: BTreeSet<usize>,
+    marker: std::marker::PhantomData<W>,
+}
+
+impl<W: Word> Lifter<W> {
+    fn new(program: &[W], graph: &ControlFlowGraph<W>) -> Self {
+        let instructions: Vec<&Instruction<W>> = graph
+            .blocks
+            .values()
+            .flat_map(|block| block.instructions.iter())
+            .collect();
+        let code = instructions
+            .iter()
+            .flat_map(|instruction| instruction.address..instruction.next_address())
+            .collect();
+        let written = instructions
+            .iter()
+            .filter_map(|instruction| {
+                let index = output_parameter(&instruction.operation)?;
+                match instruction.operation.parameter_modes()[index] {
+                    ParameterMode::Position => instruction.parameters[index].to_usize(),
+                    _ => None,
+                }
+            })
+            .collect();
+        let mut lifter = Lifter {
+            code,
+            written,
+            variables: BTreeSet::new(),
+            marker: std::marker::PhantomData,
+        };
+
+        let variables = instructions
+            .iter()
+            .flat_map(|instruction| {
+                instruction
+                    .operation
+                    .parameter_modes()
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, mode)| *mode == ParameterMode::Position)
+                    .filter(|(index, _)| !lifter.is_dynamic(instruction, *index))
+                    .filter_map(|(index, _)| instruction.parameters[index].to_usize())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|address| !lifter.code.contains(address))
+            .filter(|address| *address < program.len() || lifter.written.contains(address))
+            .collect();
+        lifter.variables = variables;
+        lifter
+    }
+
+    /// Whether the program writes over the parameter, so that its value in the image is stale
+    fn is_dynamic(&self, instruction: &Instruction<W>, index: usize) -> bool {
+        self.written.contains(&(instruction.address + 1 + index))
+    }
+
+    fn cell(&self, address: usize) -> Expression<W> {
+        if self.variables.contains(&address) {
+            Expression::Variable(address)
+        } else {
+            Expression::Cell(address)
+        }
+    }
+
+    fn parameter(&self, instruction: &Instruction<W>, index: usize) -> Expression<W> {
+        let value = &instruction.parameters[index];
+        let dynamic = self.is_dynamic(instruction, index);
+        let base = if dynamic {
+            self.cell(instruction.address + 1 + index)
+        } else {
+            Expression::Constant(value.clone())
+        };
+        match instruction.operation.parameter_modes()[index] {
+            ParameterMode::Immediate => base,
+            ParameterMode::Position => match value.to_usize() {
+                Some(address) if !dynamic => self.cell(address),
+                _ => Expression::Memory(Box::new(base)),
+            },
+            ParameterMode::Relative => Expression::Relative(Box::new(base)),
+        }
+    }
+
+    fn binary(
+        &self,
+        instruction: &Instruction<W>,
+        operator: BinaryOperator,
+    ) -> Option<Statement<W>> {
+        Some(Statement::Assign(
+            self.parameter(instruction, 2),
+            binary(
+                self.parameter(instruction, 0),
+                operator,
+                self.parameter(instruction, 1),
+            ),
+        ))
+    }
+
+    /// The statement for anything but a jump
+    fn statement(&self, instruction: &Instruction<W>) -> Option<Statement<W>> {
+        match instruction.operation {
+            Operation::Add(_, _, _) => self.binary(instruction, BinaryOperator::Add),
+            Operation::Multiply(_, _, _) => self.binary(instruction, BinaryOperator::Multiply),
+            Operation::LessThan(_, _, _) => self.binary(instruction, BinaryOperator::LessThan),
+            Operation::EqualTo(_, _, _) => self.binary(instruction, BinaryOperator::EqualTo),
+            Operation::StoreInput(_) => Some(Statement::Assign(
+                self.parameter(instruction, 0),
+                Expression::Read,
+            )),
+            Operation::ProduceOutput(_) => Some(Statement::Print(self.parameter(instruction, 0))),
+            Operation::AdjustRelativeBase(_) => {
+                Some(Statement::AdjustBase(self.parameter(instruction, 0)))
+            }
+            Operation::Terminate => Some(Statement::Halt),
+            Operation::JumpIfTrue(_, _) | Operation::JumpIfFalse(_, _) => None,
+        }
+    }
+
+    /// The condition under which a jump is taken, or `None` if it always is
+    fn condition(&self, instruction: &Instruction<W>) -> Option<Expression<W>> {
+        if !instruction.falls_through() {
+            return None;
+        }
+        match instruction.operation {
+            Operation::JumpIfTrue(_, _) => Some(self.parameter(instruction, 0)),
+            Operation::JumpIfFalse(_, _) => Some(negate(self.parameter(instruction, 0))),
+            _ => None,
+        }
+    }
+}
+
+fn output_parameter(operation: &Operation) -> Option<usize> {
+    match operation {
+        Operation::Add(_, _, _)
+        | Operation::Multiply(_, _, _)
+        | Operation::LessThan(_, _, _)
+        | Operation::EqualTo(_, _, _) => Some(2),
+        Operation::StoreInput(_) => Some(0),
+        _ => None,
+    }
+}
+
+/// Builds a binary expression, folding constants and dropping additions of zero and
+/// multiplications by one. Sums and products that would overflow the word are left unfolded,
+/// since the instruction that computes them may never run.
+fn binary<W: Word>(
+    left: Expression<W>,
+    operator: BinaryOperator,
+    right: Expression<W>,
+) -> Expression<W> {
+    use Expression::Constant;
+    if let (Constant(left_value), Constant(right_value)) = (&left, &right) {
+        let folded = match operator {
+            BinaryOperator::Add => fold(left_value, right_value, i128::checked_add),
+            BinaryOperator::Multiply => fold(left_value, right_value, i128::checked_mul),
+            BinaryOperator::LessThan => Some(boolean(left_value < right_value)),
+            BinaryOperator::EqualTo => Some(boolean(left_value == right_value)),
+        };
+        return match folded {
+            Some(value) => Constant(value),
+            None => Expression::Binary(Box::new(left), operator, Box::new(right)),
+        };
+    }
+    match (left, operator, right) {
+        (Constant(zero), BinaryOperator::Add, other)
+        | (other, BinaryOperator::Add, Constant(zero))
+            if zero.is_zero() =>
+        {
+            other
+        }
+        (Constant(one), BinaryOperator::Multiply, other)
+        | (other, BinaryOperator::Multiply, Constant(one))
+            if one.is_one() =>
+        {
+            other
+        }
+        // constants read better on the right of commutative operators
+        (Constant(constant), operator @ BinaryOperator::Add, other)
+        | (Constant(constant), operator @ BinaryOperator::Multiply, other) => {
+            Expression::Binary(Box::new(other), operator, Box::new(Constant(constant)))
+        }
+        (left, operator, right) => Expression::Binary(Box::new(left), operator, Box::new(right)),
+    }
+}
+
+fn boolean<W: Word>(value: bool) -> W {
+    if value {
+        W::one()
+    } else {
+        W::zero()
+    }
+}
+
+/// Applies the operation to two constants, unless the result does not fit in a word
+fn fold<W: Word>(left: &W, right: &W, operation: fn(i128, i128) -> Option<i128>) -> Option<W> {
+    W::from_i128(operation(left.to_i128()?, right.to_i128()?)?)
+}
+
+fn negate<W: Word>(expression: Expression<W>) -> Expression<W> {
+    match expression {
+        Expression::Not(inner) => *inner,
+        Expression::Constant(value) if value.is_zero() => Expression::Constant(W::one()),
+        Expression::Constant(_) => Expression::Constant(W::zero()),
+        expression => Expression::Not(Box::new(expression)),
+    }
+}
+
+/// Where control goes when a region of blocks finishes, and the loop that it is inside of
+#[derive(Debug, Copy, Clone)]
+struct Context {
+    header: Option<usize>,
+    exit: Option<usize>,
+    follow: usize,
+}
+
+/// Recovers structured statements from the blocks of one function, in address order
+struct Structurer<'a, W: Word> {
+    lifter: &'a Lifter<W>,
+    blocks: Vec<&'a BasicBlock<W>>,
+    labels: BTreeSet<usize>,
+    gotos: BTreeSet<usize>,
+}
+
+impl<'a, W: Word> Structurer<'a, W> {
+    fn new(lifter: &'a Lifter<W>, blocks: Vec<&'a BasicBlock<W>>) -> Self {
+        Structurer {
+            lifter,
+            blocks,
+            labels: BTreeSet::new(),
+            gotos: BTreeSet::new(),
+        }
+    }
+
+    fn structure(&mut self) -> Vec<Statement<W>> {
+        let context = Context {
+            header: None,
+            exit: None,
+            follow: usize::MAX,
+        };
+        self.region(0, usize::MAX, context)
+    }
+
+    /// The index of the first block at or after the address
+    fn index_at(&self, address: usize) -> usize {
+        self.blocks.partition_point(|block| block.start < address)
+    }
+
+    fn has_block(&self, address: usize) -> bool {
+        self.blocks
+            .get(self.index_at(address))
+            .is_some_and(|block| block.start == address)
+    }
+
+    /// The last block before `end` that jumps back to the block at `index`, if any does
+    fn latch(&self, index: usize, end: usize) -> Option<usize> {
+        let header = self.blocks[index].start;
+        (index..self.index_at(end)).rev().find(|&latch| {
+            self.blocks[latch]
+                .successors
+                .contains(&Successor::Jump(header))
+        })
+    }
+
+    /// The statement that leaves the current block for the target, if one is needed
+    fn jump_to(&mut self, target: usize, context: Context, is_last: bool) -> Option<Statement<W>> {
+        if context.header == Some(target) {
+            Some(Statement::Continue)
+        } else if context.exit == Some(target) {
+            Some(Statement::Break)
+        } else if target == context.follow && is_last {
+            None
+        } else if self.has_block(target) {
+            self.gotos.insert(target);
+            Some(Statement::Goto(target))
+        } else {
+            Some(Statement::Jump(Expression::Constant(
+                W::from_usize(target).unwrap_or_else(W::zero),
+            )))
+        }
+    }
+
+    fn region(&mut self, mut index: usize, end: usize, context: Context) -> Vec<Statement<W>> {
+        let mut statements = Vec::new();
+        while index < self.blocks.len() && self.blocks[index].start < end {
+            let block = self.blocks[index];
+            if context.header != Some(block.start) {
+                if self.labels.contains(&block.start) {
+                    statements.push(Statement::Label(block.start));
+                }
+                if let Some(latch) = self.latch(index, end) {
+                    let exit = self.blocks[latch].end();
+                    let loop_context = Context {
+                        header: Some(block.start),
+                        exit: Some(exit),
+                        follow: block.start,
+                    };
+                    let body = self.region(index, exit, loop_context);
+                    statements.push(make_loop(body));
+                    index = self.index_at(exit);
+                    continue;
+                }
+            }
+
+            statements.extend(
+                block
+                    .instructions
+                    .iter()
+                    .filter_map(|instruction| self.lifter.statement(instruction)),
+            );
+            let is_last = self
+                .blocks
+                .get(index + 1)
+                .is_none_or(|next| next.start >= end);
+            let last = block.instructions.last().expect("Blocks are never empty");
+            let jump = block
+                .successors
+                .iter()
+                .find_map(|successor| match successor {
+                    Successor::Jump(target) => Some(Some(*target)),
+                    Successor::IndirectJump => Some(None),
+                    _ => None,
+                });
+            let target = match jump {
+                Some(Some(target)) => target,
+                Some(None) => {
+                    let destination = Statement::Jump(self.lifter.parameter(last, 1));
+                    statements.push(match self.lifter.condition(last) {
+                        Some(condition) => Statement::If {
+                            condition,
+                            then: vec![destination],
+                            otherwise: Vec::new(),
+                        },
+                        None => destination,
+                    });
+                    index += 1;
+                    continue;
+                }
+                None => {
+                    if let [Successor::FallThrough(next)] = block.successors[..] {
+                        if !self.has_block(next) {
+                            statements.extend(self.jump_to(next, context, false));
+                        }
+                    }
+                    index += 1;
+                    continue;
+                }
+            };
+
+            let condition = match self.lifter.condition(last) {
+                Some(condition) => condition,
+                None => {
+                    statements.extend(self.jump_to(target, context, is_last));
+                    index += 1;
+                    continue;
+                }
+            };
+
+            let leaves_loop = context.header == Some(target) || context.exit == Some(target);
+            let nests = target > block.start
+                && target <= end
+                && (self.has_block(target) || target == end || target == context.follow);
+            if leaves_loop || !nests {
+                let then = self.jump_to(target, context, false).into_iter().collect();
+                statements.push(Statement::If {
+                    condition,
+                    then,
+                    otherwise: Vec::new(),
+                });
+                index += 1;
+                continue;
+            }
+
+            // the jump skips over the then branch, which may end by jumping over an else branch
+            let then_end = self.index_at(target);
+            let else_end = Some(then_end - 1)
+                .filter(|&then_last| then_last > index)
+                .and_then(|then_last| {
+                    let instruction = self.blocks[then_last].instructions.last()?;
+                    if instruction.falls_through() {
+                        return None;
+                    }
+                    match self.blocks[then_last].successors[..] {
+                        [Successor::Jump(after)] => Some(after),
+                        _ => None,
+                    }
+                })
+                .filter(|&after| {
+                    after > target
+                        && after <= end
+                        && (self.has_block(after) || after == end)
+                        && context.exit != Some(after)
+                        && context.header != Some(after)
+                });
+            let follow = else_end.unwrap_or(target);
+            let branch_context = Context { follow, ..context };
+            let then = self.region(index + 1, target, branch_context);
+            let otherwise = match else_end {
+                Some(after) => self.region(then_end, after, branch_context),
+                None => Vec::new(),
+            };
+            statements.push(Statement::If {
+                condition: negate(condition),
+                then,
+                otherwise,
+            });
+            index = self.index_at(follow);
+        }
+        statements
+    }
+}
+
+fn make_loop<W: Word>(mut body: Vec<Statement<W>>) -> Statement<W> {
+    if body.last() == Some(&Statement::Continue) {
+        body.pop();
+    } else if !body.last().is_some_and(diverges) {
+        body.push(Statement::Break);
+    }
+
+    // a conditional jump back to the top of the loop is a do-while
+    if let [.., Statement::If {
+        then, otherwise, ..
+    }, Statement::Break] = &body[..]
+    {
+        if then[..] == [Statement::Continue] && otherwise.is_empty() {
+            let guarded = &body[..body.len() - 2];
+            if !guarded.iter().any(continues) {
+                body.pop();
+                if let Some(Statement::If { condition, .. }) = body.pop() {
+                    return Statement::DoWhile { body, condition };
+                }
+            }
+        }
+    }
+
+    // as is a conditional break at the top of one that does not otherwise end with a break
+    if let Some(Statement::If {
+        then, otherwise, ..
+    }) = body.first()
+    {
+        if then[..] == [Statement::Break]
+            && otherwise.is_empty()
+            && body.last() != Some(&Statement::Break)
+        {
+            if let Statement::If { condition, .. } = body.remove(0) {
+                return Statement::While {
+                    condition: negate(condition),
+                    body,
+                };
+            }
+        }
+    }
+    Statement::Loop(body)
+}
+
+fn diverges<W: Word>(statement: &Statement<W>) -> bool {
+    matches!(
+        statement,
+        Statement::Halt
+            | Statement::Jump(_)
+            | Statement::Goto(_)
+            | Statement::Break
+            | Statement::Continue
+    )
+}
+
+/// Whether the statement continues the loop it is directly inside of
+fn continues<W: Word>(statement: &Statement<W>) -> bool {
+    match statement {
+        Statement::Continue => true,
+        Statement::If {
+            then, otherwise, ..
+        } => then.iter().chain(otherwise.iter()).any(continues),
+        _ => false,
+    }
+}
+
+impl fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BinaryOperator::Add => write!(f, "+"),
+            BinaryOperator::Multiply => write!(f, "*"),
+            BinaryOperator::LessThan => write!(f, "<"),
+            BinaryOperator::EqualTo => write!(f, "=="),
+        }
+    }
+}
+
+impl<W: Word> fmt::Display for Expression<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expression::Constant(value) => write!(f, "{}", value),
+            Expression::Variable(address) => write!(f, "v{}", address),
+            Expression::Cell(address) => write!(f, "mem[{}]", address),
+            Expression::Memory(address) => write!(f, "mem[{}]", address),
+            Expression::Relative(offset) => match &**offset {
+                Expression::Constant(offset) if offset.is_zero() => write!(f, "mem[rb]"),
+                Expression::Constant(offset) if offset.is_negative() => {
+                    write!(f, "mem[rb - {}]", offset.abs())
+                }
+                offset => write!(f, "mem[rb + {}]", offset),
+            },
+            Expression::Read => write!(f, "read()"),
+            Expression::Binary(left, BinaryOperator::Add, right) => match &**right {
+                Expression::Constant(value) if value.is_negative() => {
+                    write!(f, "{} - {}", left, value.abs())
+                }
+                right => write!(f, "{} + {}", left, right),
+            },
+            Expression::Binary(left, operator, right) => {
+                write!(f, "{} {} {}", left, operator, right)
+            }
+            Expression::Not(inner) => match &**inner {
+                Expression::Binary(_, _, _) => write!(f, "!({})", inner),
+                inner => write!(f, "!{}", inner),
+            },
+        }
+    }
+}
+
+fn write_statements<W: Word>(
+    f: &mut fmt::Formatter,
+    statements: &[Statement<W>],
+    depth: usize,
+) -> fmt::Result {
+    for statement in statements {
+        write_statement(f, statement, depth)?;
+    }
+    Ok(())
+}
+
+fn write_statement<W: Word>(
+    f: &mut fmt::Formatter,
+    statement: &Statement<W>,
+    depth: usize,
+) -> fmt::Result {
+    let indent = "    ".repeat(depth);
+    match statement {
+        Statement::Assign(target, value) => writeln!(f, "{}{} = {};", indent, target, value),
+        Statement::Print(value) => writeln!(f, "{}print({});", indent, value),
+        Statement::AdjustBase(value) => writeln!(f, "{}rb += {};", indent, value),
+        Statement::Halt => writeln!(f, "{}halt();", indent),
+        Statement::Jump(target) => writeln!(f, "{}jump({});", indent, target),
+        Statement::Goto(target) => writeln!(f, "{}goto L{};", indent, target),
+        Statement::Label(address) => writeln!(f, "{}L{}:", "    ".repeat(depth - 1), address),
+        Statement::Break => writeln!(f, "{}break;", indent),
+        Statement::Continue => writeln!(f, "{}continue;", indent),
+        Statement::If {
+            condition,
+            then,
+            otherwise,
+        } => {
+            writeln!(f, "{}if {} {{", indent, condition)?;
+            write_statements(f, then, depth + 1)?;
+            let mut otherwise = otherwise;
+            // chains of ifs in else branches read better as else ifs
+            while let [Statement::If {
+                condition,
+                then,
+                otherwise: rest,
+            }] = &otherwise[..]
+            {
+                writeln!(f, "{}}} else if {} {{", indent, condition)?;
+                write_statements(f, then, depth + 1)?;
+                otherwise = rest;
+            }
+            if !otherwise.is_empty() {
+                writeln!(f, "{}}} else {{", indent)?;
+                write_statements(f, otherwise, depth + 1)?;
+            }
+            writeln!(f, "{}}}", indent)
+        }
+        Statement::Loop(body) => {
+            writeln!(f, "{}loop {{", indent)?;
+            write_statements(f, body, depth + 1)?;
+            writeln!(f, "{}}}", indent)
+        }
+        Statement::While { condition, body } => {
+            writeln!(f, "{}while {} {{", indent, condition)?;
+            write_statements(f, body, depth + 1)?;
+            writeln!(f, "{}}}", indent)
+        }
+        Statement::DoWhile { body, condition } => {
+            writeln!(f, "{}do {{", indent)?;
+            write_statements(f, body, depth + 1)?;
+            writeln!(f, "{}}} while {};", indent, condition)
+        }
+    }
+}
+
+impl<W: Word> fmt::Display for Decompilation<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (address, value) in &self.variables {
+            writeln!(f, "let v{} = {};", address, value)?;
+        }
+        for function in &self.functions {
+            writeln!(f)?;
+            writeln!(f, "fn entry_{}() {{", function.entry)?;
+            write_statements(f, &function.body, 1)?;
+            writeln!(f, "}}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::assembler::assemble;
+    use crate::program::Program;
+
+    const STRUCTURED: &str = "
+                IN   [n]
+                LT   [n], #10, [small]
+                JZ   [small], #big
+                OUT  #1
+                JZ   #0, #done
+        big:    OUT  #2
+        done:
+        loop:   JZ   [n], #end
+                ADD  [n], #-1, [n]
+                OUT  [n]
+                JZ   #0, #loop
+        end:
+        again:  ADD  [count], #1, [count]
+                LT   [count], #3, [flag]
+                JNZ  [flag], #again
+                HLT
+        n:      .data 0
+        small:  .data 0
+        count:  .data 0
+        flag:   .data 0
+    ";
+
+    #[test]
+    fn it_should_recover_structured_code() {
+        let program: Vec<i64> = assemble(STRUCTURED).expect("Failed to assemble");
+        assert_eq!(
+            "let v40 = 0;
+let v41 = 0;
+let v42 = 0;
+let v43 = 0;
+
+fn entry_0() {
+    v40 = read();
+    v41 = v40 < 10;
+    if v41 {
+        print(1);
+    } else {
+        print(2);
+    }
+    while v40 {
+        v40 = v40 - 1;
+        print(v40);
+    }
+    do {
+        v42 = v42 + 1;
+        v43 = v42 < 3;
+    } while v43;
+    halt();
+}
+",
+            decompile(&program, &[0]).to_string()
+        );
+    }
+
+    #[test]
+    fn it_should_not_fold_constants_that_overflow() {
+        let program = [1101, i64::MAX, 1, 9, 1102, i64::MAX, 2, 10, 99, 0, 0];
+        assert_eq!(
+            "let v9 = 0;
+let v10 = 0;
+
+fn entry_0() {
+    v9 = 9223372036854775807 + 1;
+    v10 = 9223372036854775807 * 2;
+    halt();
+}
+",
+            decompile(&program, &[0]).to_string()
+        );
+    }
+
+    #[test]
+    fn it_should_fall_back_to_gotos() {
+        // jumps into the middle of the loop
+        let program: Vec<i64> = assemble(
+            "
+                    JNZ  [a], #inner
+            top:    OUT  #1
+            inner:  OUT  #2
+                    JNZ  [b], #top
+                    HLT
+            a:      .data 1
+            b:      .data 0
+            ",
+        )
+        .expect("Failed to assemble");
+        assert_eq!(
+            "let v11 = 1;
+let v12 = 0;
+
+fn entry_0() {
+    if !v11 {
+    L3:
+        print(1);
+    }
+    print(2);
+    if v12 {
+        goto L3;
+    }
+    halt();
+}
+",
+            decompile(&program, &[0]).to_string()
+        );
+    }
+
+    #[test]
+    fn it_should_decompile_the_day_seven_program() {
+        let program: Vec<i64> = Program::from_file("data/day-seven-input.txt")
+            .expect("Failed to load")
+            .into_words();
+        let decompilation = decompile(&program, &[0, 21]);
+        assert_eq!(
+            "let v9 = 0;
+
+fn entry_0() {
+    mem[8] = read();
+    mem[8] = mem[8] + 10;
+    jump(mem[mem[8]]);
+}
+
+fn entry_21() {
+    v9 = read();
+    v9 = v9 + 3;
+    v9 = v9 * 3;
+    v9 = v9 + 5;
+    print(v9);
+    halt();
+}
+",
+            decompilation.to_string()
+        );
+    }
+}
@@ -0,0 +1,432 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt;
+
+use crate::intcode_computer::{Operation, ParameterMode, Word};
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct DisassemblerOptions {
+    /// Only decode instructions that are reachable from address zero by falling through or by
+    /// following jumps with immediate targets, treating every other word as data
+    pub follow_jumps: bool,
+    /// Insert a comment wherever the listing switches between code and data
+    pub mark_boundaries: bool,
+    /// Further addresses to follow jumps from besides address zero, such as the targets of jumps
+    /// whose destinations are only known at run time
+    pub entry_points: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Instruction<W: Word = i64> {
+    pub address: usize,
+    pub operation: Operation,
+    pub code: W,
+    pub parameters: Vec<W>,
+}
+
+impl<W: Word> Instruction<W> {
+    /// Decodes the instruction at the address, if the word there is a valid operation and all of
+    /// its parameters are inside of the program
+    pub fn decode(program: &[W], address: usize) -> Option<Self> {
+        let code = program.get(address)?;
+        let operation = Operation::decode(code).ok()?;
+        let parameters = program
+            .get(address + 1..address + 1 + operation.number_of_parameters())?
+            .to_vec();
+        Some(Instruction {
+            address,
+            operation,
+            code: code.clone(),
+            parameters,
+        })
+    }
+
+    /// The address directly after the instruction and its parameters
+    pub fn next_address(&self) -> usize {
+        self.address + 1 + self.parameters.len()
+    }
+
+    pub fn is_jump(&self) -> bool {
+        matches!(
+            self.operation,
+            Operation::JumpIfTrue(_, _) | Operation::JumpIfFalse(_, _)
+        )
+    }
+
+    /// The destination of a jump, if it can be known without running the program
+    pub fn jump_target(&self) -> Option<usize> {
+        match self.operation {
+            Operation::JumpIfTrue(_, ParameterMode::Immediate)
+            | Operation::JumpIfFalse(_, ParameterMode::Immediate) => self.parameters[1].to_usize(),
+            _ => None,
+        }
+    }
+
+    /// Whether a jump can ever be taken, which is not the case for one whose immediate condition
+    /// always makes it fall through
+    pub fn can_jump(&self) -> bool {
+        match self.operation {
+            Operation::JumpIfTrue(ParameterMode::Immediate, _) => !self.parameters[0].is_zero(),
+            Operation::JumpIfFalse(ParameterMode::Immediate, _) => self.parameters[0].is_zero(),
+            _ => self.is_jump(),
+        }
+    }
+
+    /// Whether execution can continue with the instruction directly after this one
+    pub fn falls_through(&self) -> bool {
+        match self.operation {
+            Operation::Terminate => false,
+            Operation::JumpIfTrue(ParameterMode::Immediate, _) => self.parameters[0].is_zero(),
+            Operation::JumpIfFalse(ParameterMode::Immediate, _) => !self.parameters[0].is_zero(),
+            _ => true,
+        }
+    }
+}
+
+impl<W: Word> fmt::Display for Instruction<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parameters: Vec<String> = self
+            .operation
+            .parameter_modes()
+            .into_iter()
+            .zip(self.parameters.iter())
+            .map(|(mode, value)| format_parameter(mode, value))
+            .collect();
+        if parameters.is_empty() {
+            write!(f, "{}", mnemonic(&self.operation))
+        } else {
+            write!(
+                f,
+                "{:<4} {}",
+                mnemonic(&self.operation),
+                parameters.join(", ")
+            )
+        }
+    }
+}
+
+pub fn mnemonic(operation: &Operation) -> &'static str {
+    match operation {
+        Operation::Add(_, _, _) => "ADD",
+        Operation::Multiply(_, _, _) => "MUL",
+        Operation::StoreInput(_) => "IN",
+        Operation::ProduceOutput(_) => "OUT",
+        Operation::JumpIfTrue(_, _) => "JNZ",
+        Operation::JumpIfFalse(_, _) => "JZ",
+        Operation::LessThan(_, _, _) => "LT",
+        Operation::EqualTo(_, _, _) => "EQ",
+        Operation::AdjustRelativeBase(_) => "ARB",
+        Operation::Terminate => "HLT",
+    }
+}
+
+/// Formats a parameter as `[address]` in position mode, `#value` in immediate mode and
+/// `[rb+offset]` in relative mode
+pub fn format_parameter<W: Word>(mode: ParameterMode, value: &W) -> String {
+    match mode {
+        ParameterMode::Position => format!("[{}]", value),
+        ParameterMode::Immediate => format!("#{}", value),
+        ParameterMode::Relative if value.is_negative() => format!("[rb-{}]", value.abs()),
+        ParameterMode::Relative => format!("[rb+{}]", value),
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Line<W: Word = i64> {
+    Instruction(Instruction<W>),
+    Data { address: usize, value: W },
+}
+
+impl<W: Word> Line<W> {
+    pub fn address(&self) -> usize {
+        match self {
+            Line::Instruction(instruction) => instruction.address,
+            Line::Data { address, .. } => *address,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Disassembly<W: Word = i64> {
+    pub lines: Vec<Line<W>>,
+    pub jump_targets: BTreeSet<usize>,
+    mark_boundaries: bool,
+}
+
+pub fn disassemble<W: Word>(program: &[W], options: &DisassemblerOptions) -> Disassembly<W> {
+    let instructions = if options.follow_jumps {
+        let entry_points: Vec<usize> = std::iter::once(0)
+            .chain(options.entry_points.iter().copied())
+            .collect();
+        reachable_instructions(program, &entry_points)
+    } else {
+        sequential_instructions(program)
+    };
+    let jump_targets = instructions
+        .iter()
+        .flatten()
+        .filter_map(Instruction::jump_target)
+        .filter(|target| *target < program.len())
+        .collect();
+
+    let mut lines = Vec::new();
+    let mut address = 0;
+    while address < program.len() {
+        match &instructions[address] {
+            Some(instruction) => {
+                address = instruction.next_address();
+                lines.push(Line::Instruction(instruction.clone()));
+            }
+            None => {
+                lines.push(Line::Data {
+                    address,
+                    value: program[address].clone(),
+                });
+                address += 1;
+            }
+        }
+    }
+    Disassembly {
+        lines,
+        jump_targets,
+        mark_boundaries: options.mark_boundaries,
+    }
+}
+
+fn sequential_instructions<W: Word>(program: &[W]) -> Vec<Option<Instruction<W>>> {
+    let mut instructions = vec![None; program.len()];
+    let mut address = 0;
+    while address < program.len() {
+        match Instruction::decode(program, address) {
+            Some(instruction) => {
+                let next_address = instruction.next_address();
+                instructions[address] = Some(instruction);
+                address = next_address;
+            }
+            None => address += 1,
+        }
+    }
+    instructions
+}
+
+/// Decodes the instructions that can be reached from the entry points by falling through or by
+/// following jumps with immediate targets, indexed by address
+pub(crate) fn reachable_instructions<W: Word>(
+    program: &[W],
+    entry_points: &[usize],
+) -> Vec<Option<Instruction<W>>> {
+    let mut instructions = vec![None; program.len()];
+    let mut covered = vec![false; program.len()];
+    let mut pending: VecDeque<usize> = entry_points.iter().copied().collect();
+    while let Some(address) = pending.pop_front() {
+        if address >= program.len() || covered[address] {
+            continue;
+        }
+        let instruction = match Instruction::decode(program, address) {
+            Some(instruction) => instruction,
+            None => continue,
+        };
+        let words = address..instruction.next_address();
+        if words.clone().any(|word| covered[word]) {
+            continue;
+        }
+        words.for_each(|word| covered[word] = true);
+        pending.extend(instruction.jump_target().filter(|_| instruction.can_jump()));
+        if instruction.falls_through() {
+            pending.push_back(instruction.next_address());
+        }
+        instructions[address] = Some(instruction);
+    }
+    instructions
+}
+
+impl<W: Word> fmt::Display for Disassembly<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut previous_was_code = None;
+        for line in &self.lines {
+            let is_code = matches!(line, Line::Instruction(_));
+            if self.mark_boundaries && previous_was_code != Some(is_code) {
+                writeln!(f, "; ---- {} ----", if is_code { "code" } else { "data" })?;
+            }
+            previous_was_code = Some(is_code);
+
+            if self.jump_targets.contains(&line.address()) {
+                writeln!(f, "L{}:", line.address())?;
+            }
+            match line {
+                Line::Instruction(instruction) => {
+                    let words: Vec<String> = std::iter::once(&instruction.code)
+                        .chain(instruction.parameters.iter())
+                        .map(W::to_string)
+                        .collect();
+                    writeln!(
+                        f,
+                        "{:>6}: {:<24} {}",
+                        instruction.address,
+                        words.join(","),
+                        instruction
+                    )?;
+                }
+                Line::Data { address, value } => {
+                    writeln!(f, "{:>6}: {:<24} DATA {}", address, value, value)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::program::Program;
+
+    #[test]
+    fn it_should_format_instructions() {
+        let program = vec![1002, 4, 3, 4, 21101, -2, 7, -1, 1105, 1, 0, 99];
+        let format = |address| {
+            Instruction::decode(&program, address)
+                .expect("Failed to decode instruction")
+                .to_string()
+        };
+        assert_eq!("MUL  [4], #3, [4]", format(0));
+        assert_eq!("ADD  #-2, #7, [rb-1]", format(4));
+        assert_eq!("JNZ  #1, #0", format(8));
+        assert_eq!("HLT", format(11));
+    }
+
+    #[test]
+    fn it_should_not_decode_invalid_or_truncated_instructions() {
+        let program = vec![42, 1, 0, 0];
+        assert_eq!(None, Instruction::decode(&program, 0));
+        assert_eq!(None, Instruction::decode(&program, 1));
+        assert_eq!(None, Instruction::decode(&program, 4));
+    }
+
+    #[test]
+    fn it_should_disassemble_sequentially() {
+        let program = vec![3, 9, 1005, 9, 7, 104, 0, 99, 42, 0];
+        let disassembly = disassemble(&program, &DisassemblerOptions::default());
+        assert_eq!(
+            vec![0, 2, 5, 7, 8, 9],
+            disassembly
+                .lines
+                .iter()
+                .map(Line::address)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Line::Data {
+                address: 8,
+                value: 42
+            },
+            disassembly.lines[4]
+        );
+        assert_eq!(
+            vec![7].into_iter().collect::<BTreeSet<_>>(),
+            disassembly.jump_targets
+        );
+    }
+
+    #[test]
+    fn it_should_follow_jumps() {
+        let program = vec![1105, 1, 5, 2, 2, 104, 7, 99, 1, 1, 1];
+        let disassembly = disassemble(
+            &program,
+            &DisassemblerOptions {
+                follow_jumps: true,
+                mark_boundaries: true,
+                ..DisassemblerOptions::default()
+            },
+        );
+        assert_eq!(
+            "; ---- code ----
+     0: 1105,1,5                 JNZ  #1, #5
+; ---- data ----
+     3: 2                        DATA 2
+     4: 2                        DATA 2
+; ---- code ----
+L5:
+     5: 104,7                    OUT  #7
+     7: 99                       HLT
+; ---- data ----
+     8: 1                        DATA 1
+     9: 1                        DATA 1
+    10: 1                        DATA 1
+",
+            disassembly.to_string()
+        );
+    }
+
+    #[test]
+    fn it_should_follow_jumps_from_further_entry_points() {
+        let program = vec![1105, 1, 5, 2, 2, 104, 7, 99, 104, 1, 99];
+        let disassembly = disassemble(
+            &program,
+            &DisassemblerOptions {
+                follow_jumps: true,
+                entry_points: vec![8],
+                ..DisassemblerOptions::default()
+            },
+        );
+        assert_eq!(
+            "     0: 1105,1,5                 JNZ  #1, #5
+     3: 2                        DATA 2
+     4: 2                        DATA 2
+L5:
+     5: 104,7                    OUT  #7
+     7: 99                       HLT
+     8: 104,1                    OUT  #1
+    10: 99                       HLT
+",
+            disassembly.to_string()
+        );
+    }
+
+    #[test]
+    fn it_should_disassemble_the_day_five_program() {
+        let program: Vec<i64> = include_str!("../data/day-five-input.txt")
+            .parse::<Program>()
+            .expect("Invalid program")
+            .into_words();
+
+        let disassembly = disassemble(&program, &DisassemblerOptions::default());
+        assert_eq!(
+            Line::Instruction(Instruction {
+                address: 0,
+                operation: Operation::StoreInput(ParameterMode::Position),
+                code: 3,
+                parameters: vec![225],
+            }),
+            disassembly.lines[0]
+        );
+        assert_eq!(
+            "JNZ  #0, #99999",
+            Instruction::decode(&program, 238)
+                .expect("Failed to decode instruction")
+                .to_string()
+        );
+        assert!(disassembly.lines.contains(&Line::Instruction(Instruction {
+            address: 238,
+            operation: Operation::JumpIfTrue(ParameterMode::Immediate, ParameterMode::Immediate),
+            code: 1105,
+            parameters: vec![0, 99999],
+        })));
+
+        // the first instruction patches the opcode at address 6, so it cannot be followed statically
+        let disassembly = disassemble(
+            &program,
+            &DisassemblerOptions {
+                follow_jumps: true,
+                ..DisassemblerOptions::default()
+            },
+        );
+        assert_eq!(
+            Line::Data {
+                address: 6,
+                value: 1100
+            },
+            disassembly.lines[2]
+        );
+    }
+}
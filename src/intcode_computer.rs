@@ -1,105 +1,205 @@
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use std::collections::VecDeque;
 use std::convert::TryFrom;
 
-#[derive(Debug, PartialEq, Error)]
-pub enum ExecutionError {
+use crate::disassembler::Instruction;
+
+mod compiled;
+mod cycle;
+mod decode_cache;
+mod io;
+mod journal;
+mod memory;
+mod profile;
+mod self_modification;
+mod snapshot;
+mod trace;
+mod word;
+
+pub use compiled::{CompiledComputer, CompiledProgram};
+use cycle::CycleDetector;
+use decode_cache::{DecodeCache, DecodedInstruction, Operand};
+pub use io::{FnInput, FnOutput, InputSource, IterInput, OutputSink, ReaderInput, WriterOutput};
+use journal::Journal;
+pub use journal::JournalEntry;
+use memory::Memory;
+pub use profile::{BranchCounts, Profile, ProfileReport};
+use self_modification::CodeTracker;
+pub use self_modification::{SelfModification, SelfModificationPolicy};
+pub use snapshot::{Snapshot, SnapshotError, SnapshotFormat, SNAPSHOT_VERSION};
+pub use trace::{TraceRecord, TraceWrite, Tracer};
+pub use word::Word;
+
+#[derive(Debug, PartialEq, Clone, Error)]
+pub enum ExecutionError<W: Word = i64> {
     #[error("Unsupported operation code {code} found at position {index}")]
-    InvalidOperationCode { index: usize, code: i32 },
+    InvalidOperationCode { index: usize, code: W },
     #[error("Operation attempted to index position {index}, but program has the length of {program_length}")]
-    IndexOutsideOfProgram { index: i32, program_length: usize },
+    IndexOutsideOfProgram { index: W, program_length: usize },
     #[error("Invalid operation index found for operation at position {index}")]
-    InvalidOperationIndex { index: i32 },
+    InvalidOperationIndex { index: W },
     #[error("No input available for operation at position {index}")]
     InvalidRequestForInput { index: usize },
+    #[error("Input or output failed for operation at position {index}: {reason}")]
+    DeviceFailure { index: usize, reason: String },
+    #[error("Step limit exceeded after {steps} steps at position {index}")]
+    StepLimitExceeded { steps: u64, index: usize },
+    #[error("Infinite loop at position {index}: step {step} repeats the state of step {previous_step} without any input or output in between")]
+    InfiniteLoop {
+        index: usize,
+        step: u64,
+        previous_step: u64,
+    },
+    #[error("Operation at position {index} attempted to write over code at position {address}")]
+    SelfModifyingCode { index: usize, address: usize },
+    #[error("Compiled code diverged from the interpreter at position {index} on step {step}")]
+    CompilationMismatch { index: usize, step: u64 },
 }
 
-#[derive(Debug, PartialEq)]
-enum Operation {
-    Add(ParameterMode, ParameterMode),
-    Multiply(ParameterMode, ParameterMode),
-    StoreInput,
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub enum Operation {
+    Add(ParameterMode, ParameterMode, ParameterMode),
+    Multiply(ParameterMode, ParameterMode, ParameterMode),
+    StoreInput(ParameterMode),
     ProduceOutput(ParameterMode),
     JumpIfTrue(ParameterMode, ParameterMode),
     JumpIfFalse(ParameterMode, ParameterMode),
-    LessThan(ParameterMode, ParameterMode),
-    EqualTo(ParameterMode, ParameterMode),
+    LessThan(ParameterMode, ParameterMode, ParameterMode),
+    EqualTo(ParameterMode, ParameterMode, ParameterMode),
+    AdjustRelativeBase(ParameterMode),
     Terminate,
 }
 
 impl Operation {
-    fn number_of_parameters(&self) -> usize {
+    pub fn number_of_parameters(&self) -> usize {
         match self {
-            Self::Add(_, _) | Self::Multiply(_, _) | Self::LessThan(_, _) | Self::EqualTo(_, _) => {
-                3
-            }
+            Self::Add(_, _, _)
+            | Self::Multiply(_, _, _)
+            | Self::LessThan(_, _, _)
+            | Self::EqualTo(_, _, _) => 3,
             Self::JumpIfTrue(_, _) | Self::JumpIfFalse(_, _) => 2,
-            Self::StoreInput | Self::ProduceOutput(_) => 1,
+            Self::StoreInput(_) | Self::ProduceOutput(_) | Self::AdjustRelativeBase(_) => 1,
             Self::Terminate => 0,
         }
     }
-}
 
-impl TryFrom<i32> for Operation {
-    type Error = String;
+    pub fn parameter_modes(&self) -> Vec<ParameterMode> {
+        match *self {
+            Self::Add(left, right, output)
+            | Self::Multiply(left, right, output)
+            | Self::LessThan(left, right, output)
+            | Self::EqualTo(left, right, output) => vec![left, right, output],
+            Self::JumpIfTrue(condition, target) | Self::JumpIfFalse(condition, target) => {
+                vec![condition, target]
+            }
+            Self::StoreInput(mode) | Self::ProduceOutput(mode) | Self::AdjustRelativeBase(mode) => {
+                vec![mode]
+            }
+            Self::Terminate => vec![],
+        }
+    }
 
-    fn try_from(value: i32) -> Result<Self, Self::Error> {
-        let code = value % 100;
-        let mut parameter_modes = value / 100;
-        match code {
-            1 => {
+    /// Encodes the operation and its parameter modes as the word that `decode` would accept
+    pub fn encode(&self) -> i32 {
+        let code = match self {
+            Self::Add(_, _, _) => 1,
+            Self::Multiply(_, _, _) => 2,
+            Self::StoreInput(_) => 3,
+            Self::ProduceOutput(_) => 4,
+            Self::JumpIfTrue(_, _) => 5,
+            Self::JumpIfFalse(_, _) => 6,
+            Self::LessThan(_, _, _) => 7,
+            Self::EqualTo(_, _, _) => 8,
+            Self::AdjustRelativeBase(_) => 9,
+            Self::Terminate => 99,
+        };
+        let modes = self
+            .parameter_modes()
+            .into_iter()
+            .rev()
+            .fold(0, |modes, mode| modes * 10 + i32::from(mode));
+        modes * 100 + code
+    }
+
+    pub fn decode<W: Word>(value: &W) -> Result<Self, String> {
+        let (mut parameter_modes, code) = value.div_rem(&W::from(100));
+        match code.to_i32() {
+            Some(1) => {
                 let left_mode = extract_parameter_mode(&mut parameter_modes)?;
                 let right_mode = extract_parameter_mode(&mut parameter_modes)?;
-                Ok(Self::Add(left_mode, right_mode))
+                let output_mode = extract_output_mode(&mut parameter_modes)?;
+                Ok(Self::Add(left_mode, right_mode, output_mode))
             }
-            2 => {
+            Some(2) => {
                 let left_mode = extract_parameter_mode(&mut parameter_modes)?;
                 let right_mode = extract_parameter_mode(&mut parameter_modes)?;
-                Ok(Self::Multiply(left_mode, right_mode))
+                let output_mode = extract_output_mode(&mut parameter_modes)?;
+                Ok(Self::Multiply(left_mode, right_mode, output_mode))
             }
-            3 => Ok(Self::StoreInput),
-            4 => {
+            Some(3) => {
+                let output_mode = extract_output_mode(&mut parameter_modes)?;
+                Ok(Self::StoreInput(output_mode))
+            }
+            Some(4) => {
                 let mode = extract_parameter_mode(&mut parameter_modes)?;
                 Ok(Self::ProduceOutput(mode))
             }
-            5 => {
+            Some(5) => {
                 let true_mode = extract_parameter_mode(&mut parameter_modes)?;
                 let jump_mode = extract_parameter_mode(&mut parameter_modes)?;
                 Ok(Self::JumpIfTrue(true_mode, jump_mode))
             }
-            6 => {
+            Some(6) => {
                 let false_mode = extract_parameter_mode(&mut parameter_modes)?;
                 let jump_mode = extract_parameter_mode(&mut parameter_modes)?;
                 Ok(Self::JumpIfFalse(false_mode, jump_mode))
             }
-            7 => {
+            Some(7) => {
                 let left_mode = extract_parameter_mode(&mut parameter_modes)?;
                 let right_mode = extract_parameter_mode(&mut parameter_modes)?;
-                Ok(Self::LessThan(left_mode, right_mode))
+                let output_mode = extract_output_mode(&mut parameter_modes)?;
+                Ok(Self::LessThan(left_mode, right_mode, output_mode))
             }
-            8 => {
+            Some(8) => {
                 let left_mode = extract_parameter_mode(&mut parameter_modes)?;
                 let right_mode = extract_parameter_mode(&mut parameter_modes)?;
-                Ok(Self::EqualTo(left_mode, right_mode))
+                let output_mode = extract_output_mode(&mut parameter_modes)?;
+                Ok(Self::EqualTo(left_mode, right_mode, output_mode))
+            }
+            Some(9) => {
+                let mode = extract_parameter_mode(&mut parameter_modes)?;
+                Ok(Self::AdjustRelativeBase(mode))
             }
-            99 => Ok(Self::Terminate),
-            n => Err(format!("Invalid Operation {}", n)),
+            Some(99) => Ok(Self::Terminate),
+            _ => Err(format!("Invalid Operation {}", code)),
         }
     }
 }
 
-fn extract_parameter_mode(parameter_modes: &mut i32) -> Result<ParameterMode, String> {
-    let parameter_mode = ParameterMode::try_from(*parameter_modes % 10)?;
-    *parameter_modes /= 10;
+fn extract_parameter_mode<W: Word>(parameter_modes: &mut W) -> Result<ParameterMode, String> {
+    let (remaining_modes, mode) = parameter_modes.div_rem(&W::from(10));
+    let parameter_mode = ParameterMode::try_from(mode.to_i32().unwrap_or(-1))?;
+    *parameter_modes = remaining_modes;
 
     Ok(parameter_mode)
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
-enum ParameterMode {
+fn extract_output_mode<W: Word>(parameter_modes: &mut W) -> Result<ParameterMode, String> {
+    match extract_parameter_mode(parameter_modes)? {
+        ParameterMode::Immediate => Err(String::from(
+            "Invalid Parameter Mode 1 for an output parameter",
+        )),
+        mode => Ok(mode),
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub enum ParameterMode {
     Position,
     Immediate,
+    Relative,
 }
 
 impl TryFrom<i32> for ParameterMode {
@@ -109,110 +209,693 @@ impl TryFrom<i32> for ParameterMode {
         match value {
             0 => Ok(Self::Position),
             1 => Ok(Self::Immediate),
+            2 => Ok(Self::Relative),
             n => Err(format!("Invalid Parameter Mode {}", n)),
         }
     }
 }
 
+impl From<ParameterMode> for i32 {
+    fn from(mode: ParameterMode) -> i32 {
+        match mode {
+            ParameterMode::Position => 0,
+            ParameterMode::Immediate => 1,
+            ParameterMode::Relative => 2,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 enum ExecutionStatus {
     Ongoing,
+    AwaitingInput,
     Terminated,
 }
 
-pub struct IntcodeComputer {
+/// The reason that an `IntcodeComputer` stopped executing. Unless the program has halted,
+/// execution can be resumed from the same program counter by calling `execute` again.
+#[derive(Debug, PartialEq)]
+pub enum ExecutionState<W: Word = i64> {
+    AwaitingInput,
+    ProducedOutput(W),
+    Halted,
+}
+
+/// A memory cell that was overwritten by an instruction
+#[derive(Debug, PartialEq, Clone)]
+pub struct MemoryWrite<W: Word = i64> {
+    pub address: usize,
+    pub previous: W,
+    pub value: W,
+}
+
+/// A description of a single executed instruction. The operands are the values of the
+/// parameters that the instruction read and the resolved addresses of the ones it wrote to, in
+/// the order that they appear in the instruction.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ExecutionEvent<W: Word = i64> {
+    pub address: usize,
+    pub operation: Operation,
+    pub operands: Vec<W>,
+    pub memory_write: Option<MemoryWrite<W>>,
+    pub input: Option<W>,
+    pub output: Option<W>,
+    pub program_counter: usize,
+}
+
+impl<W: Word> ExecutionEvent<W> {
+    fn new(address: usize, operation: Operation) -> Self {
+        ExecutionEvent {
+            address,
+            operation,
+            operands: Vec::with_capacity(operation.number_of_parameters()),
+            memory_write: None,
+            input: None,
+            output: None,
+            program_counter: address,
+        }
+    }
+}
+
+/// The outcome of executing a single instruction with `IntcodeComputer::step`
+#[derive(Debug, PartialEq)]
+pub enum StepResult<W: Word = i64> {
+    Executed(ExecutionEvent<W>),
+    AwaitingInput,
+    Halted,
+}
+
+/// An Intcode machine. Cloning it copies the machine state and the journal, but not the tracer.
+pub struct IntcodeComputer<W: Word = i64> {
     program_counter: usize,
-    memory: Vec<i32>,
-    input: VecDeque<i32>,
-    output: VecDeque<i32>,
+    relative_base: W,
+    memory: Memory<W>,
+    input: VecDeque<W>,
+    output: VecDeque<W>,
+    halted: bool,
+    steps: u64,
+    tracer: Option<Tracer>,
+    journal: Option<Journal<W>>,
+    step_limit: Option<u64>,
+    cycle_detector: Option<CycleDetector>,
+    profile: Option<Profile>,
+    code_tracker: Option<CodeTracker<W>>,
+    decode_cache: Option<DecodeCache<W>>,
+}
+
+impl<W: Word> Clone for IntcodeComputer<W> {
+    fn clone(&self) -> Self {
+        IntcodeComputer {
+            program_counter: self.program_counter,
+            relative_base: self.relative_base.clone(),
+            memory: self.memory.clone(),
+            input: self.input.clone(),
+            output: self.output.clone(),
+            halted: self.halted,
+            steps: self.steps,
+            tracer: None,
+            journal: self.journal.clone(),
+            step_limit: self.step_limit,
+            cycle_detector: self.cycle_detector.clone(),
+            profile: self.profile.clone(),
+            code_tracker: self.code_tracker.clone(),
+            decode_cache: self.decode_cache.clone(),
+        }
+    }
 }
 
-impl IntcodeComputer {
-    pub fn new(memory: Vec<i32>) -> IntcodeComputer {
+impl<W: Word> IntcodeComputer<W> {
+    pub fn new(memory: Vec<W>) -> IntcodeComputer<W> {
         IntcodeComputer {
             program_counter: 0,
-            memory,
+            relative_base: W::zero(),
+            memory: Memory::new(memory),
             input: VecDeque::new(),
             output: VecDeque::new(),
+            halted: false,
+            steps: 0,
+            tracer: None,
+            journal: None,
+            step_limit: None,
+            cycle_detector: None,
+            profile: None,
+            code_tracker: None,
+            decode_cache: None,
         }
     }
-    pub fn new_with_input(memory: Vec<i32>, input: VecDeque<i32>) -> IntcodeComputer {
+    pub fn new_with_input(memory: Vec<W>, input: VecDeque<W>) -> IntcodeComputer<W> {
         IntcodeComputer {
             program_counter: 0,
-            memory,
+            relative_base: W::zero(),
+            memory: Memory::new(memory),
             input,
             output: VecDeque::new(),
+            halted: false,
+            steps: 0,
+            tracer: None,
+            journal: None,
+            step_limit: None,
+            cycle_detector: None,
+            profile: None,
+            code_tracker: None,
+            decode_cache: None,
         }
     }
 
-    pub fn execute(&mut self) -> Result<VecDeque<i32>, ExecutionError> {
+    /// Restores a computer from a snapshot, without a tracer or a journal
+    pub fn from_snapshot(snapshot: Snapshot<W>) -> IntcodeComputer<W> {
+        IntcodeComputer {
+            program_counter: snapshot.program_counter,
+            relative_base: snapshot.relative_base,
+            memory: Memory::from_parts(snapshot.image, snapshot.cells, snapshot.memory_length),
+            input: snapshot.input,
+            output: snapshot.output,
+            halted: snapshot.halted,
+            steps: snapshot.steps,
+            tracer: None,
+            journal: None,
+            step_limit: None,
+            cycle_detector: None,
+            profile: None,
+            code_tracker: None,
+            decode_cache: None,
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot<W> {
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            program_counter: self.program_counter,
+            relative_base: self.relative_base.clone(),
+            image: self.memory.image().to_vec(),
+            cells: self.memory.cells_outside_image(),
+            memory_length: self.memory.len(),
+            input: self.input.clone(),
+            output: self.output.clone(),
+            halted: self.halted,
+            steps: self.steps,
+        }
+    }
+
+    pub fn push_input(&mut self, value: W) {
+        self.input.push_back(value);
+    }
+
+    pub fn take_output(&mut self) -> VecDeque<W> {
+        if let Some(journal) = &mut self.journal {
+            journal.mark_outputs_delivered(self.output.len());
+        }
+        std::mem::take(&mut self.output)
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    pub fn program_counter(&self) -> usize {
+        self.program_counter
+    }
+
+    /// The number of instructions that have been executed
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Records every instruction executed from now on with the tracer
+    pub fn set_tracer(&mut self, tracer: Tracer) {
+        self.tracer = Some(tracer);
+    }
+
+    /// Stops tracing, flushing and returning the tracer
+    pub fn take_tracer(&mut self) -> std::io::Result<Option<Tracer>> {
+        if let Some(tracer) = &mut self.tracer {
+            tracer.flush()?;
+        }
+        Ok(self.tracer.take())
+    }
+
+    /// Fails with `StepLimitExceeded` instead of executing any more instructions once `limit`
+    /// steps have been executed in total, or never stops if there is no limit
+    pub fn set_step_limit(&mut self, limit: Option<u64>) {
+        self.step_limit = limit;
+    }
+
+    /// Fails with `InfiniteLoop` when the machine comes back to a program counter, relative base,
+    /// memory and input queue that it has already been in since it last read input or produced
+    /// output. States are compared by their hashes, and hashing all of memory on every step is
+    /// slow, so this is best kept for tests and debugging.
+    pub fn enable_cycle_detection(&mut self) {
+        self.cycle_detector = Some(CycleDetector::default());
+    }
+
+    /// Counts every instruction executed from now on by opcode, parameter modes and address, along
+    /// with how often each conditional jump is taken
+    pub fn enable_profiling(&mut self) {
+        self.profile = Some(Profile::default());
+    }
+
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    /// Stops profiling, returning what was counted
+    pub fn take_profile(&mut self) -> Option<Profile> {
+        self.profile.take()
+    }
+
+    /// Summarises the profile, disassembling the `top` most executed addresses from memory as it
+    /// is now
+    pub fn profile_report(&self, top: usize) -> Option<ProfileReport<'_, W>> {
+        let profile = self.profile.as_ref()?;
+        let hot_spots = profile
+            .hottest_addresses(top)
+            .into_iter()
+            .map(|(address, executed)| {
+                let words: Vec<W> = (address..address + 4)
+                    .map(|address| self.memory.get(address).clone())
+                    .collect();
+                let instruction = Instruction::decode(&words, 0)
+                    .map(|instruction| Instruction {
+                        address,
+                        ..instruction
+                    })
+                    .ok_or_else(|| words[0].clone());
+                (address, executed, instruction)
+            })
+            .collect();
+        Some(ProfileReport { profile, hot_spots })
+    }
+
+    /// Decides what happens when the program writes over an address that it has already executed.
+    /// Executed addresses are only tracked from the first time that a policy other than `Allow`
+    /// is set.
+    pub fn set_self_modification_policy(&mut self, policy: SelfModificationPolicy) {
+        match (&mut self.code_tracker, policy) {
+            (_, SelfModificationPolicy::Allow) => self.code_tracker = None,
+            (Some(tracker), _) => tracker.policy = policy,
+            (None, _) => self.code_tracker = Some(CodeTracker::new(policy)),
+        }
+    }
+
+    /// The writes over executed code that were let through with the `Warn` policy
+    pub fn self_modifications(&self) -> &[SelfModification<W>] {
+        self.code_tracker
+            .as_ref()
+            .map_or(&[], |tracker| &tracker.report[..])
+    }
+
+    /// Caches every instruction of the program image from now on as it is decoded, with its
+    /// operands resolved as far as they can be, until something writes over it. Instructions past
+    /// the end of the image are decoded every time. This pays off in loops, while a machine that
+    /// only executes each instruction a few times is faster without it.
+    pub fn enable_decode_cache(&mut self) {
+        if self.decode_cache.is_none() {
+            self.decode_cache = Some(DecodeCache::new(self.memory.image().len()));
+        }
+    }
+
+    /// Decodes every address of the program image up front into the decode cache, enabling it.
+    /// Clones of the computer share what was decoded, so a machine that is set up once and then
+    /// cloned for every run never decodes an instruction again unless the program writes over it.
+    pub fn predecode(&mut self) {
+        self.decode_cache = Some(DecodeCache::predecode(
+            &self.memory,
+            self.memory.image().len(),
+        ));
+    }
+
+    /// Keeps a history of up to `capacity` executed instructions, so that they can be undone
+    /// with `step_back` and `rewind_to`
+    pub fn enable_journal(&mut self, capacity: usize) {
+        self.journal = Some(Journal::new(capacity));
+    }
+
+    /// The earliest step that the journal can rewind to, if it holds any history
+    pub fn earliest_step(&self) -> Option<u64> {
+        self.journal.as_ref().and_then(Journal::earliest_step)
+    }
+
+    /// Undoes the most recently executed instruction, restoring the memory it wrote, the input
+    /// it consumed and the program counter and relative base from before it executed. Output
+    /// that has already been taken from the output queue stays delivered.
+    pub fn step_back(&mut self) -> Option<JournalEntry<W>> {
+        let entry = self.journal.as_mut()?.pop()?;
+        if let Some(write) = &entry.memory_write {
+            self.store(write.address, write.previous.clone());
+        }
+        if let Some(relative_base) = &entry.previous_relative_base {
+            self.relative_base = relative_base.clone();
+        }
+        if let Some(input) = &entry.input {
+            self.input.push_front(input.clone());
+        }
+        if entry.output.is_some() && !entry.output_delivered {
+            self.output.pop_back();
+        }
+        if entry.halted {
+            self.halted = false;
+        }
+        self.program_counter = entry.address;
+        self.steps = entry.step;
+        self.clear_cycle_detector();
+        Some(entry)
+    }
+
+    /// Undoes instructions until only `step` of them have been executed, returning false without
+    /// changing anything if the journal does not reach back that far
+    pub fn rewind_to(&mut self, step: u64) -> bool {
+        if step > self.steps
+            || self
+                .earliest_step()
+                .map_or(step != self.steps, |earliest| step < earliest)
+        {
+            return false;
+        }
+        while self.steps > step {
+            self.step_back();
+        }
+        true
+    }
+
+    /// Moves execution to the address, which also allows a halted program to run again. Like
+    /// any other manual edit, this clears the journal.
+    pub fn set_program_counter(&mut self, address: usize) {
+        self.program_counter = address;
+        self.halted = false;
+        self.clear_journal();
+        self.clear_cycle_detector();
+    }
+
+    pub fn relative_base(&self) -> &W {
+        &self.relative_base
+    }
+
+    pub fn set_relative_base(&mut self, relative_base: W) {
+        self.relative_base = relative_base;
+        self.clear_journal();
+        self.clear_cycle_detector();
+    }
+
+    /// Reads a memory cell, where cells that have never been written to are zero
+    pub fn read_memory(&self, address: usize) -> &W {
+        self.memory.get(address)
+    }
+
+    pub fn write_memory(&mut self, address: usize, value: W) {
+        self.store(address, value);
+        self.clear_journal();
+        self.clear_cycle_detector();
+    }
+
+    /// The number of cells up to and including the highest one that has been written to
+    pub fn memory_len(&self) -> usize {
+        self.memory.len()
+    }
+
+    /// The input that has been provided but not consumed by the program yet
+    pub fn input_queue(&self) -> &VecDeque<W> {
+        &self.input
+    }
+
+    /// Gives access to the pending input for editing. Like any other manual edit, this clears
+    /// the journal.
+    pub fn input_queue_mut(&mut self) -> &mut VecDeque<W> {
+        self.clear_journal();
+        self.clear_cycle_detector();
+        &mut self.input
+    }
+
+    /// The output that has been produced but not taken yet
+    pub fn output_queue(&self) -> &VecDeque<W> {
+        &self.output
+    }
+
+    /// Executes until the program halts or requests input that is not available yet
+    pub fn execute(&mut self) -> Result<ExecutionState<W>, ExecutionError<W>> {
+        self.run(false)
+    }
+
+    /// Executes until the program halts, requests input that is not available yet, or produces
+    /// output, which is returned instead of being added to the output queue
+    pub fn execute_until_output(&mut self) -> Result<ExecutionState<W>, ExecutionError<W>> {
+        self.run(true)
+    }
+
+    /// Executes the program to completion, failing if it requests input that was not provided
+    pub fn run_to_halt(&mut self) -> Result<VecDeque<W>, ExecutionError<W>> {
+        match self.execute()? {
+            ExecutionState::Halted => Ok(self.take_output()),
+            _ => Err(ExecutionError::InvalidRequestForInput {
+                index: self.program_counter,
+            }),
+        }
+    }
+
+    /// Executes while reading input from the source whenever the program requests it and sending
+    /// output to the sink as soon as it is produced, until the program halts or the source has no
+    /// input available
+    pub fn run_with(
+        &mut self,
+        input: &mut impl InputSource<W>,
+        output: &mut impl OutputSink<W>,
+    ) -> Result<ExecutionState<W>, ExecutionError<W>> {
         loop {
-            let operation_code =
-                self.memory
-                    .get(self.program_counter)
-                    .copied()
-                    .ok_or_else(|| ExecutionError::InvalidOperationIndex {
-                        index: self.program_counter as i32,
-                    })?;
-            let operation = Operation::try_from(operation_code).map_err(|_| {
-                ExecutionError::InvalidOperationCode {
+            match self.step()? {
+                StepResult::Executed(ExecutionEvent {
+                    address,
+                    output: Some(_),
+                    ..
+                }) => {
+                    if let Some(value) = self.deliver_output() {
+                        output.send_output(value).map_err(|error| {
+                            ExecutionError::DeviceFailure {
+                                index: address,
+                                reason: error.to_string(),
+                            }
+                        })?;
+                    }
+                }
+                StepResult::Executed(_) => {}
+                StepResult::AwaitingInput => {
+                    let value =
+                        input
+                            .next_input()
+                            .map_err(|error| ExecutionError::DeviceFailure {
+                                index: self.program_counter,
+                                reason: error.to_string(),
+                            })?;
+                    match value {
+                        Some(value) => self.push_input(value),
+                        None => return Ok(ExecutionState::AwaitingInput),
+                    }
+                }
+                StepResult::Halted => return Ok(ExecutionState::Halted),
+            }
+        }
+    }
+
+    /// Executes exactly one instruction, describing what it did
+    pub fn step(&mut self) -> Result<StepResult<W>, ExecutionError<W>> {
+        if self.halted {
+            return Ok(StepResult::Halted);
+        }
+        if self.step_limit.is_some_and(|limit| self.steps >= limit) {
+            return Err(ExecutionError::StepLimitExceeded {
+                steps: self.steps,
+                index: self.program_counter,
+            });
+        }
+        let state = self.cycle_detector.is_some().then(|| self.state_hash());
+        if let (Some(detector), Some(state)) = (&mut self.cycle_detector, state) {
+            if let Some(previous_step) = detector.visit(state, self.steps) {
+                return Err(ExecutionError::InfiniteLoop {
                     index: self.program_counter,
-                    code: operation_code,
+                    step: self.steps,
+                    previous_step,
+                });
+            }
+        }
+        let instruction = self.decode()?;
+        let operation = instruction.operation;
+        if let Some(tracker) = &mut self.code_tracker {
+            tracker.mark_executed(self.program_counter, operation.number_of_parameters());
+        }
+        let mut event = ExecutionEvent::new(self.program_counter, operation);
+        let previous_relative_base = match (&self.journal, operation) {
+            (Some(_), Operation::AdjustRelativeBase(_)) => Some(self.relative_base.clone()),
+            _ => None,
+        };
+        match self.perform_operation(&instruction, &mut event)? {
+            ExecutionStatus::Ongoing => {}
+            ExecutionStatus::AwaitingInput => {
+                // asking again once input has arrived is not a loop
+                if let (Some(detector), Some(state)) = (&mut self.cycle_detector, state) {
+                    detector.forget(state);
                 }
-            })?;
-            let result = self.perform_operation(operation)?;
-            if ExecutionStatus::Terminated == result {
-                return Ok(self.output.clone());
+                return Ok(StepResult::AwaitingInput);
             }
+            ExecutionStatus::Terminated => self.halted = true,
+        }
+        if event.input.is_some() || event.output.is_some() {
+            self.clear_cycle_detector();
+        }
+        event.program_counter = self.program_counter;
+        if let Some(profile) = &mut self.profile {
+            profile.record(&event);
+        }
+        if let Some(tracer) = &mut self.tracer {
+            tracer
+                .record(self.steps, &instruction.code, &event)
+                .map_err(|error| ExecutionError::DeviceFailure {
+                    index: event.address,
+                    reason: error.to_string(),
+                })?;
+        }
+        if let Some(journal) = &mut self.journal {
+            journal.record(JournalEntry {
+                step: self.steps,
+                address: event.address,
+                memory_write: event.memory_write.clone(),
+                previous_relative_base,
+                input: event.input.clone(),
+                output: event.output.clone(),
+                halted: self.halted,
+                output_delivered: false,
+            });
+        }
+        self.steps += 1;
+        Ok(StepResult::Executed(event))
+    }
+
+    fn run(&mut self, pause_on_output: bool) -> Result<ExecutionState<W>, ExecutionError<W>> {
+        loop {
+            match self.step()? {
+                StepResult::Executed(ExecutionEvent {
+                    output: Some(output),
+                    ..
+                }) if pause_on_output => {
+                    self.deliver_output();
+                    return Ok(ExecutionState::ProducedOutput(output));
+                }
+                StepResult::Executed(_) => {}
+                StepResult::AwaitingInput => return Ok(ExecutionState::AwaitingInput),
+                StepResult::Halted => return Ok(ExecutionState::Halted),
+            }
+        }
+    }
+
+    /// Removes the most recent output from the output queue to hand it straight to the caller
+    fn deliver_output(&mut self) -> Option<W> {
+        if let Some(journal) = &mut self.journal {
+            journal.mark_outputs_delivered(1);
+        }
+        self.output.pop_back()
+    }
+
+    fn clear_journal(&mut self) {
+        if let Some(journal) = &mut self.journal {
+            journal.clear();
+        }
+    }
+
+    fn clear_cycle_detector(&mut self) {
+        if let Some(detector) = &mut self.cycle_detector {
+            detector.clear();
+        }
+    }
+
+    fn state_hash(&self) -> u64 {
+        CycleDetector::hash_state(&(
+            self.program_counter,
+            &self.relative_base,
+            &self.memory,
+            &self.input,
+        ))
+    }
+
+    /// The instruction at the program counter, from the decode cache if it has been decoded
+    /// before
+    fn decode(&mut self) -> Result<DecodedInstruction<W>, ExecutionError<W>> {
+        let address = self.program_counter;
+        if let Some(instruction) = self
+            .decode_cache
+            .as_ref()
+            .and_then(|cache| cache.get(address))
+        {
+            return Ok(instruction.clone());
+        }
+        let instruction = DecodedInstruction::decode(&self.memory, address).map_err(|code| {
+            ExecutionError::InvalidOperationCode {
+                index: address,
+                code,
+            }
+        })?;
+        if let Some(cache) = &mut self.decode_cache {
+            cache.insert(address, instruction.clone());
         }
+        Ok(instruction)
     }
 
     fn perform_operation(
         &mut self,
-        operation: Operation,
-    ) -> Result<ExecutionStatus, ExecutionError> {
+        instruction: &DecodedInstruction<W>,
+        event: &mut ExecutionEvent<W>,
+    ) -> Result<ExecutionStatus, ExecutionError<W>> {
+        let operation = instruction.operation;
+        let [first, second, third] = &instruction.operands;
         match operation {
-            Operation::Add(left_mode, right_mode) => {
-                self.perform_function(left_mode, right_mode, std::ops::Add::add)?;
+            Operation::Add(_, _, _) => {
+                self.perform_function(first, second, third, std::ops::Add::add, event)?;
             }
-            Operation::Multiply(left_mode, right_mode) => {
-                self.perform_function(left_mode, right_mode, std::ops::Mul::mul)?;
+            Operation::Multiply(_, _, _) => {
+                self.perform_function(first, second, third, std::ops::Mul::mul, event)?;
             }
-            Operation::StoreInput => {
-                if let Some(input) = self.input.pop_front() {
-                    let output_index =
-                        self.fetch_parameter(ParameterMode::Immediate, self.program_counter + 1)?;
-                    self.set_memory(output_index, input)?;
+            Operation::StoreInput(_) => {
+                if let Some(input) = self.input.front().cloned() {
+                    let output_index = self.fetch_output_index(first);
+                    event.operands.push(output_index.clone());
+                    event.memory_write = Some(self.set_memory(output_index, input.clone())?);
+                    event.input = Some(input);
+                    self.input.pop_front();
                 } else {
-                    return Err(ExecutionError::InvalidRequestForInput {
-                        index: self.program_counter,
-                    });
+                    return Ok(ExecutionStatus::AwaitingInput);
                 }
             }
-            Operation::ProduceOutput(mode) => {
-                let output = self.fetch_parameter(mode, self.program_counter + 1)?;
+            Operation::ProduceOutput(_) => {
+                let output = self.fetch_parameter(first)?;
+                event.operands.push(output.clone());
+                event.output = Some(output.clone());
                 self.output.push_back(output);
             }
-            Operation::JumpIfTrue(true_mode, jump_mode) => {
-                return self.perform_jump(true_mode, jump_mode, |value| value != 0)
+            Operation::JumpIfTrue(_, _) => {
+                return self.perform_jump(first, second, |value| !value.is_zero(), event)
             }
-            Operation::JumpIfFalse(false_mode, jump_mode) => {
-                return self.perform_jump(false_mode, jump_mode, |value| value == 0)
+            Operation::JumpIfFalse(_, _) => {
+                return self.perform_jump(first, second, |value| value.is_zero(), event)
             }
-            Operation::LessThan(left_mode, right_mode) => {
+            Operation::LessThan(_, _, _) => {
                 self.perform_function(
-                    left_mode,
-                    right_mode,
+                    first,
+                    second,
+                    third,
                     wrap_boolean_fn(|left, right| left < right),
+                    event,
                 )?;
             }
-            Operation::EqualTo(left_mode, right_mode) => {
+            Operation::EqualTo(_, _, _) => {
                 self.perform_function(
-                    left_mode,
-                    right_mode,
+                    first,
+                    second,
+                    third,
                     wrap_boolean_fn(|left, right| left == right),
+                    event,
                 )?;
             }
+            Operation::AdjustRelativeBase(_) => {
+                let adjustment = self.fetch_parameter(first)?;
+                event.operands.push(adjustment.clone());
+                self.relative_base = self.relative_base.clone() + adjustment;
+            }
             Operation::Terminate => {
                 self.program_counter += 1 + operation.number_of_parameters();
                 return Ok(ExecutionStatus::Terminated);
@@ -222,61 +905,66 @@ impl IntcodeComputer {
         Ok(ExecutionStatus::Ongoing)
     }
 
-    fn fetch_parameter(&self, mode: ParameterMode, index: usize) -> Result<i32, ExecutionError> {
-        let value = self.memory.get(index).copied().ok_or_else(|| {
-            ExecutionError::IndexOutsideOfProgram {
-                index: index as i32,
-                program_length: self.memory.len(),
-            }
-        })?;
-        match mode {
-            ParameterMode::Position => {
-                if value < 0 {
-                    return Err(ExecutionError::IndexOutsideOfProgram {
-                        index: value,
-                        program_length: self.memory.len(),
-                    });
-                }
-                self.fetch_parameter(ParameterMode::Immediate, value as usize)
+    fn fetch_parameter(&self, operand: &Operand<W>) -> Result<W, ExecutionError<W>> {
+        match operand {
+            Operand::Immediate(value) => Ok(value.clone()),
+            Operand::Address(address) => Ok(self.memory.get(*address).clone()),
+            Operand::Relative(offset) => {
+                self.fetch_from_address(self.relative_base.clone() + offset.clone())
             }
-            ParameterMode::Immediate => Ok(value),
+            Operand::Invalid(value) => self.fetch_from_address(value.clone()),
+        }
+    }
+
+    fn fetch_from_address(&self, address: W) -> Result<W, ExecutionError<W>> {
+        let index = self.to_index(address)?;
+        Ok(self.memory.get(index).clone())
+    }
+
+    fn fetch_output_index(&self, operand: &Operand<W>) -> W {
+        match operand {
+            Operand::Address(address) => W::from_usize(*address).expect("Addresses fit in a word"),
+            Operand::Relative(offset) => self.relative_base.clone() + offset.clone(),
+            Operand::Immediate(value) | Operand::Invalid(value) => value.clone(),
         }
     }
 
     fn perform_function(
         &mut self,
-        left_mode: ParameterMode,
-        right_mode: ParameterMode,
-        operation: impl FnOnce(i32, i32) -> i32,
-    ) -> Result<(), ExecutionError> {
-        let left = self.fetch_parameter(left_mode, self.program_counter + 1)?;
-        let right = self.fetch_parameter(right_mode, self.program_counter + 2)?;
-        let output_index = self
-            .memory
-            .get(self.program_counter + 3)
-            .copied()
-            .ok_or_else(|| ExecutionError::IndexOutsideOfProgram {
-                index: (self.program_counter + 3) as i32,
-                program_length: self.memory.len(),
-            })?;
-        self.set_memory(output_index, operation(left, right))
+        left: &Operand<W>,
+        right: &Operand<W>,
+        output: &Operand<W>,
+        operation: impl FnOnce(W, W) -> W,
+        event: &mut ExecutionEvent<W>,
+    ) -> Result<(), ExecutionError<W>> {
+        let left = self.fetch_parameter(left)?;
+        let right = self.fetch_parameter(right)?;
+        let output_index = self.fetch_output_index(output);
+        event.operands.push(left.clone());
+        event.operands.push(right.clone());
+        event.operands.push(output_index.clone());
+        event.memory_write = Some(self.set_memory(output_index, operation(left, right))?);
+        Ok(())
     }
 
     fn perform_jump(
         &mut self,
-        true_mode: ParameterMode,
-        jump_mode: ParameterMode,
-        operation: impl FnOnce(i32) -> bool,
-    ) -> Result<ExecutionStatus, ExecutionError> {
-        let truth_value = self.fetch_parameter(true_mode, self.program_counter + 1)?;
-        if operation(truth_value) {
-            let new_program_counter = self.fetch_parameter(jump_mode, self.program_counter + 2)?;
-            if new_program_counter < 0 {
-                return Err(ExecutionError::InvalidOperationIndex {
-                    index: new_program_counter,
-                });
-            }
-            self.program_counter = new_program_counter as usize;
+        condition: &Operand<W>,
+        target: &Operand<W>,
+        operation: impl FnOnce(&W) -> bool,
+        event: &mut ExecutionEvent<W>,
+    ) -> Result<ExecutionStatus, ExecutionError<W>> {
+        let truth_value = self.fetch_parameter(condition)?;
+        event.operands.push(truth_value.clone());
+        if operation(&truth_value) {
+            let new_program_counter = self.fetch_parameter(target)?;
+            event.operands.push(new_program_counter.clone());
+            self.program_counter =
+                new_program_counter
+                    .to_usize()
+                    .ok_or(ExecutionError::InvalidOperationIndex {
+                        index: new_program_counter,
+                    })?;
             return Ok(ExecutionStatus::Ongoing);
         }
 
@@ -284,29 +972,59 @@ impl IntcodeComputer {
         Ok(ExecutionStatus::Ongoing)
     }
 
-    fn set_memory(&mut self, index: i32, value: i32) -> Result<(), ExecutionError> {
-        if index < 0 {
-            return Err(ExecutionError::IndexOutsideOfProgram {
-                index,
-                program_length: self.memory.len(),
-            });
+    fn set_memory(&mut self, index: W, value: W) -> Result<MemoryWrite<W>, ExecutionError<W>> {
+        let address = self.to_index(index)?;
+        let write = MemoryWrite {
+            address,
+            previous: self.memory.get(address).clone(),
+            value,
+        };
+        if let Some(tracker) = &mut self.code_tracker {
+            if tracker.is_executed(address) {
+                match tracker.policy {
+                    SelfModificationPolicy::Allow => {}
+                    SelfModificationPolicy::Warn => tracker.report.push(SelfModification {
+                        step: self.steps,
+                        index: self.program_counter,
+                        write: write.clone(),
+                    }),
+                    SelfModificationPolicy::Error => {
+                        return Err(ExecutionError::SelfModifyingCode {
+                            index: self.program_counter,
+                            address,
+                        })
+                    }
+                }
+            }
         }
-        self.memory
-            .get_mut(index as usize)
-            .map(|output| *output = value)
+        self.store(address, write.value.clone());
+        Ok(write)
+    }
+
+    /// Writes to memory, dropping any decoded instruction that covers the address
+    fn store(&mut self, address: usize, value: W) {
+        self.memory.set(address, value);
+        if let Some(cache) = &mut self.decode_cache {
+            cache.invalidate(address);
+        }
+    }
+
+    fn to_index(&self, address: W) -> Result<usize, ExecutionError<W>> {
+        address
+            .to_usize()
             .ok_or_else(|| ExecutionError::IndexOutsideOfProgram {
-                index,
+                index: address,
                 program_length: self.memory.len(),
             })
     }
 }
 
-fn wrap_boolean_fn(to_wrap: impl Fn(i32, i32) -> bool) -> impl Fn(i32, i32) -> i32 {
+fn wrap_boolean_fn<W: Word>(to_wrap: impl Fn(&W, &W) -> bool) -> impl Fn(W, W) -> W {
     move |left, right| {
-        if to_wrap(left, right) {
-            1
+        if to_wrap(&left, &right) {
+            W::one()
         } else {
-            0
+            W::zero()
         }
     }
 }
@@ -315,76 +1033,187 @@ fn wrap_boolean_fn(to_wrap: impl Fn(i32, i32) -> bool) -> impl Fn(i32, i32) -> i
 mod tests {
     use super::*;
 
+    use num::BigInt;
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
     #[test]
     fn it_should_properly_convert_operations() {
         assert_eq!(
             Ok(Operation::Add(
                 ParameterMode::Immediate,
+                ParameterMode::Position,
                 ParameterMode::Position
             )),
-            Operation::try_from(101)
+            Operation::decode(&101)
         );
         assert_eq!(
             Ok(Operation::Multiply(
                 ParameterMode::Position,
-                ParameterMode::Immediate
+                ParameterMode::Immediate,
+                ParameterMode::Position
             )),
-            Operation::try_from(1002)
+            Operation::decode(&1002)
+        );
+        assert_eq!(
+            Ok(Operation::StoreInput(ParameterMode::Position)),
+            Operation::decode(&3)
         );
-        assert_eq!(Ok(Operation::StoreInput), Operation::try_from(3));
         assert_eq!(
             Ok(Operation::ProduceOutput(ParameterMode::Position)),
-            Operation::try_from(4)
+            Operation::decode(&4)
         );
         assert_eq!(
             Ok(Operation::JumpIfTrue(
                 ParameterMode::Immediate,
                 ParameterMode::Position
             )),
-            Operation::try_from(105)
+            Operation::decode(&105)
         );
         assert_eq!(
             Ok(Operation::JumpIfFalse(
                 ParameterMode::Immediate,
                 ParameterMode::Position
             )),
-            Operation::try_from(106)
+            Operation::decode(&106)
         );
         assert_eq!(
             Ok(Operation::LessThan(
                 ParameterMode::Immediate,
+                ParameterMode::Position,
                 ParameterMode::Position
             )),
-            Operation::try_from(107)
+            Operation::decode(&107)
         );
         assert_eq!(
             Ok(Operation::EqualTo(
                 ParameterMode::Immediate,
+                ParameterMode::Position,
                 ParameterMode::Position
             )),
-            Operation::try_from(108)
+            Operation::decode(&108)
+        );
+        assert_eq!(
+            Ok(Operation::AdjustRelativeBase(ParameterMode::Relative)),
+            Operation::decode(&209)
+        );
+        assert_eq!(
+            Ok(Operation::Add(
+                ParameterMode::Relative,
+                ParameterMode::Immediate,
+                ParameterMode::Relative
+            )),
+            Operation::decode(&21201)
+        );
+        assert_eq!(
+            Ok(Operation::StoreInput(ParameterMode::Relative)),
+            Operation::decode(&203)
+        );
+        assert_eq!(Ok(Operation::Terminate), Operation::decode(&99));
+    }
+
+    #[test]
+    fn it_should_encode_operations() {
+        assert_eq!(
+            1002,
+            Operation::Multiply(
+                ParameterMode::Position,
+                ParameterMode::Immediate,
+                ParameterMode::Position
+            )
+            .encode()
+        );
+        assert_eq!(
+            21107,
+            Operation::LessThan(
+                ParameterMode::Immediate,
+                ParameterMode::Immediate,
+                ParameterMode::Relative
+            )
+            .encode()
+        );
+        assert_eq!(203, Operation::StoreInput(ParameterMode::Relative).encode());
+        assert_eq!(99, Operation::Terminate.encode());
+        for code in &[1, 2, 3, 4, 5, 6, 7, 8, 9, 99, 1001, 22201, 1105, 209, 104] {
+            let operation = Operation::decode(code).expect("Failed to decode operation");
+            assert_eq!(*code, operation.encode());
+        }
+    }
+
+    #[test]
+    fn it_should_list_parameter_modes() {
+        assert_eq!(
+            vec![
+                ParameterMode::Relative,
+                ParameterMode::Immediate,
+                ParameterMode::Position
+            ],
+            Operation::decode(&1201).unwrap().parameter_modes()
+        );
+        assert_eq!(
+            vec![ParameterMode::Immediate, ParameterMode::Relative],
+            Operation::decode(&2105).unwrap().parameter_modes()
+        );
+        assert_eq!(
+            Vec::<ParameterMode>::new(),
+            Operation::Terminate.parameter_modes()
         );
-        assert_eq!(Ok(Operation::Terminate), Operation::try_from(99));
     }
 
     #[test]
     fn it_should_fail_for_an_invalid_operation_code() {
         assert_eq!(
             Err(String::from("Invalid Operation 10")),
-            Operation::try_from(10)
+            Operation::decode(&10)
         );
         assert_eq!(
             Err(String::from("Invalid Parameter Mode 3")),
-            Operation::try_from(301)
+            Operation::decode(&301)
+        );
+        assert_eq!(
+            Err(String::from(
+                "Invalid Parameter Mode 1 for an output parameter"
+            )),
+            Operation::decode(&10001)
+        );
+        assert_eq!(
+            Err(String::from(
+                "Invalid Parameter Mode 1 for an output parameter"
+            )),
+            Operation::decode(&103)
         );
     }
 
-    fn setup_computer(memory: Vec<i32>) -> IntcodeComputer {
+    impl<W: Word> IntcodeComputer<W> {
+        fn perform_test_operation(
+            &mut self,
+            operation: Operation,
+        ) -> Result<ExecutionStatus, ExecutionError<W>> {
+            let code = self.memory.get(self.program_counter).clone();
+            let instruction =
+                DecodedInstruction::new(code, operation, &self.memory, self.program_counter);
+            let mut event = ExecutionEvent::new(self.program_counter, operation);
+            self.perform_operation(&instruction, &mut event)
+        }
+    }
+
+    fn setup_computer(memory: Vec<i64>) -> IntcodeComputer {
         IntcodeComputer {
             program_counter: 0,
-            memory,
+            relative_base: 0,
+            memory: Memory::new(memory),
             input: VecDeque::new(),
             output: VecDeque::new(),
+            halted: false,
+            steps: 0,
+            tracer: None,
+            journal: None,
+            step_limit: None,
+            cycle_detector: None,
+            profile: None,
+            code_tracker: None,
+            decode_cache: None,
         }
     }
 
@@ -392,12 +1221,13 @@ mod tests {
     fn it_should_perform_an_addition() {
         let mut computer = setup_computer(vec![1, 0, 0, 3]);
         let status = computer
-            .perform_operation(Operation::Add(
+            .perform_test_operation(Operation::Add(
+                ParameterMode::Position,
                 ParameterMode::Position,
                 ParameterMode::Position,
             ))
             .expect("Failed to execute operation");
-        assert_eq!(vec![1, 0, 0, 2], computer.memory);
+        assert_eq!(vec![1, 0, 0, 2], computer.memory.to_vec());
         assert_eq!(4, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
     }
@@ -406,12 +1236,13 @@ mod tests {
     fn it_should_perform_an_addition_in_immediate_mode() {
         let mut computer = setup_computer(vec![1101, 2, 2, 3]);
         let status = computer
-            .perform_operation(Operation::Add(
+            .perform_test_operation(Operation::Add(
                 ParameterMode::Immediate,
                 ParameterMode::Immediate,
+                ParameterMode::Position,
             ))
             .expect("Failed to execute operation");
-        assert_eq!(vec![1101, 2, 2, 4], computer.memory);
+        assert_eq!(vec![1101, 2, 2, 4], computer.memory.to_vec());
         assert_eq!(4, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
     }
@@ -420,12 +1251,13 @@ mod tests {
     fn it_should_perform_a_multiplication() {
         let mut computer = setup_computer(vec![2, 0, 0, 3]);
         let status = computer
-            .perform_operation(Operation::Multiply(
+            .perform_test_operation(Operation::Multiply(
+                ParameterMode::Position,
                 ParameterMode::Position,
                 ParameterMode::Position,
             ))
             .expect("Failed to execute operation");
-        assert_eq!(vec![2, 0, 0, 4], computer.memory);
+        assert_eq!(vec![2, 0, 0, 4], computer.memory.to_vec());
         assert_eq!(4, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
     }
@@ -434,12 +1266,13 @@ mod tests {
     fn it_should_perform_a_multiplication_in_immediate_mode() {
         let mut computer = setup_computer(vec![1102, 3, 3, 3]);
         let status = computer
-            .perform_operation(Operation::Multiply(
+            .perform_test_operation(Operation::Multiply(
                 ParameterMode::Immediate,
                 ParameterMode::Immediate,
+                ParameterMode::Position,
             ))
             .expect("Failed to execute operation");
-        assert_eq!(vec![1102, 3, 3, 9], computer.memory);
+        assert_eq!(vec![1102, 3, 3, 9], computer.memory.to_vec());
         assert_eq!(4, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
     }
@@ -448,14 +1281,24 @@ mod tests {
     fn it_should_retrieve_input() {
         let mut computer = IntcodeComputer {
             program_counter: 0,
-            memory: vec![3, 3, 0, 0],
+            relative_base: 0,
+            memory: Memory::new(vec![3, 3, 0, 0]),
             input: vec![5].into(),
             output: VecDeque::new(),
+            halted: false,
+            steps: 0,
+            tracer: None,
+            journal: None,
+            step_limit: None,
+            cycle_detector: None,
+            profile: None,
+            code_tracker: None,
+            decode_cache: None,
         };
         let status = computer
-            .perform_operation(Operation::StoreInput)
+            .perform_test_operation(Operation::StoreInput(ParameterMode::Position))
             .expect("Failed to execute operation");
-        assert_eq!(vec![3, 3, 0, 5], computer.memory);
+        assert_eq!(vec![3, 3, 0, 5], computer.memory.to_vec());
         assert_eq!(VecDeque::new(), computer.input);
         assert_eq!(2, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
@@ -465,14 +1308,24 @@ mod tests {
     fn it_should_produce_output() {
         let mut computer = IntcodeComputer {
             program_counter: 0,
-            memory: vec![4, 3, 0, 5],
+            relative_base: 0,
+            memory: Memory::new(vec![4, 3, 0, 5]),
             input: VecDeque::new(),
             output: VecDeque::new(),
+            halted: false,
+            steps: 0,
+            tracer: None,
+            journal: None,
+            step_limit: None,
+            cycle_detector: None,
+            profile: None,
+            code_tracker: None,
+            decode_cache: None,
         };
         let status = computer
-            .perform_operation(Operation::ProduceOutput(ParameterMode::Position))
+            .perform_test_operation(Operation::ProduceOutput(ParameterMode::Position))
             .expect("Failed to execute operation");
-        assert_eq!(vec![4, 3, 0, 5], computer.memory);
+        assert_eq!(vec![4, 3, 0, 5], computer.memory.to_vec());
         assert_eq!(VecDeque::from(vec![5]), computer.output);
         assert_eq!(2, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
@@ -482,14 +1335,24 @@ mod tests {
     fn it_should_produce_output_in_immediate_mode() {
         let mut computer = IntcodeComputer {
             program_counter: 0,
-            memory: vec![4, 3, 0, 5],
+            relative_base: 0,
+            memory: Memory::new(vec![4, 3, 0, 5]),
             input: VecDeque::new(),
             output: VecDeque::new(),
+            halted: false,
+            steps: 0,
+            tracer: None,
+            journal: None,
+            step_limit: None,
+            cycle_detector: None,
+            profile: None,
+            code_tracker: None,
+            decode_cache: None,
         };
         let status = computer
-            .perform_operation(Operation::ProduceOutput(ParameterMode::Immediate))
+            .perform_test_operation(Operation::ProduceOutput(ParameterMode::Immediate))
             .expect("Failed to execute operation");
-        assert_eq!(vec![4, 3, 0, 5], computer.memory);
+        assert_eq!(vec![4, 3, 0, 5], computer.memory.to_vec());
         assert_eq!(VecDeque::from(vec![3]), computer.output);
         assert_eq!(2, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
@@ -500,24 +1363,24 @@ mod tests {
         // perform jump
         let mut computer = setup_computer(vec![5, 1, 3, 2]);
         let status = computer
-            .perform_operation(Operation::JumpIfTrue(
+            .perform_test_operation(Operation::JumpIfTrue(
                 ParameterMode::Position,
                 ParameterMode::Position,
             ))
             .expect("Failed to execute operation");
-        assert_eq!(vec![5, 1, 3, 2], computer.memory);
+        assert_eq!(vec![5, 1, 3, 2], computer.memory.to_vec());
         assert_eq!(2, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
 
         // no jump performed
         let mut computer = setup_computer(vec![5, 4, 3, 2, 0]);
         let status = computer
-            .perform_operation(Operation::JumpIfTrue(
+            .perform_test_operation(Operation::JumpIfTrue(
                 ParameterMode::Position,
                 ParameterMode::Position,
             ))
             .expect("Failed to execute operation");
-        assert_eq!(vec![5, 4, 3, 2, 0], computer.memory);
+        assert_eq!(vec![5, 4, 3, 2, 0], computer.memory.to_vec());
         assert_eq!(3, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
     }
@@ -527,24 +1390,24 @@ mod tests {
         // perform jump
         let mut computer = setup_computer(vec![1105, 1, 4, 2, 0]);
         let status = computer
-            .perform_operation(Operation::JumpIfTrue(
+            .perform_test_operation(Operation::JumpIfTrue(
                 ParameterMode::Immediate,
                 ParameterMode::Immediate,
             ))
             .expect("Failed to execute operation");
-        assert_eq!(vec![1105, 1, 4, 2, 0], computer.memory);
+        assert_eq!(vec![1105, 1, 4, 2, 0], computer.memory.to_vec());
         assert_eq!(4, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
 
         // no jump performed
         let mut computer = setup_computer(vec![1105, 0, 4, 2, 0]);
         let status = computer
-            .perform_operation(Operation::JumpIfTrue(
+            .perform_test_operation(Operation::JumpIfTrue(
                 ParameterMode::Immediate,
                 ParameterMode::Immediate,
             ))
             .expect("Failed to execute operation");
-        assert_eq!(vec![1105, 0, 4, 2, 0], computer.memory);
+        assert_eq!(vec![1105, 0, 4, 2, 0], computer.memory.to_vec());
         assert_eq!(3, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
     }
@@ -554,24 +1417,24 @@ mod tests {
         // perform jump
         let mut computer = setup_computer(vec![6, 4, 2, 2, 0]);
         let status = computer
-            .perform_operation(Operation::JumpIfFalse(
+            .perform_test_operation(Operation::JumpIfFalse(
                 ParameterMode::Position,
                 ParameterMode::Position,
             ))
             .expect("Failed to execute operation");
-        assert_eq!(vec![6, 4, 2, 2, 0], computer.memory);
+        assert_eq!(vec![6, 4, 2, 2, 0], computer.memory.to_vec());
         assert_eq!(2, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
 
         // no jump performed
         let mut computer = setup_computer(vec![6, 4, 2, 2, 1]);
         let status = computer
-            .perform_operation(Operation::JumpIfFalse(
+            .perform_test_operation(Operation::JumpIfFalse(
                 ParameterMode::Position,
                 ParameterMode::Position,
             ))
             .expect("Failed to execute operation");
-        assert_eq!(vec![6, 4, 2, 2, 1], computer.memory);
+        assert_eq!(vec![6, 4, 2, 2, 1], computer.memory.to_vec());
         assert_eq!(3, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
     }
@@ -581,24 +1444,24 @@ mod tests {
         // perform jump
         let mut computer = setup_computer(vec![1106, 0, 4, 2]);
         let status = computer
-            .perform_operation(Operation::JumpIfFalse(
+            .perform_test_operation(Operation::JumpIfFalse(
                 ParameterMode::Immediate,
                 ParameterMode::Immediate,
             ))
             .expect("Failed to execute operation");
-        assert_eq!(vec![1106, 0, 4, 2], computer.memory);
+        assert_eq!(vec![1106, 0, 4, 2], computer.memory.to_vec());
         assert_eq!(4, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
 
         // no jump performed
         let mut computer = setup_computer(vec![1106, 1, 3, 2]);
         let status = computer
-            .perform_operation(Operation::JumpIfFalse(
+            .perform_test_operation(Operation::JumpIfFalse(
                 ParameterMode::Immediate,
                 ParameterMode::Immediate,
             ))
             .expect("Failed to execute operation");
-        assert_eq!(vec![1106, 1, 3, 2], computer.memory);
+        assert_eq!(vec![1106, 1, 3, 2], computer.memory.to_vec());
         assert_eq!(3, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
     }
@@ -608,24 +1471,26 @@ mod tests {
         // less than
         let mut computer = setup_computer(vec![7, 4, 5, 6, 0, 1, 0]);
         let status = computer
-            .perform_operation(Operation::LessThan(
+            .perform_test_operation(Operation::LessThan(
+                ParameterMode::Position,
                 ParameterMode::Position,
                 ParameterMode::Position,
             ))
             .expect("Failed to execute operation");
-        assert_eq!(vec![7, 4, 5, 6, 0, 1, 1], computer.memory);
+        assert_eq!(vec![7, 4, 5, 6, 0, 1, 1], computer.memory.to_vec());
         assert_eq!(4, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
 
         // not less than
         let mut computer = setup_computer(vec![7, 4, 5, 6, 1, 0, 1]);
         let status = computer
-            .perform_operation(Operation::LessThan(
+            .perform_test_operation(Operation::LessThan(
+                ParameterMode::Position,
                 ParameterMode::Position,
                 ParameterMode::Position,
             ))
             .expect("Failed to execute operation");
-        assert_eq!(vec![7, 4, 5, 6, 1, 0, 0], computer.memory);
+        assert_eq!(vec![7, 4, 5, 6, 1, 0, 0], computer.memory.to_vec());
         assert_eq!(4, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
     }
@@ -635,24 +1500,26 @@ mod tests {
         // less than
         let mut computer = setup_computer(vec![1107, 0, 1, 4, 0]);
         let status = computer
-            .perform_operation(Operation::LessThan(
+            .perform_test_operation(Operation::LessThan(
                 ParameterMode::Immediate,
                 ParameterMode::Immediate,
+                ParameterMode::Position,
             ))
             .expect("Failed to execute operation");
-        assert_eq!(vec![1107, 0, 1, 4, 1], computer.memory);
+        assert_eq!(vec![1107, 0, 1, 4, 1], computer.memory.to_vec());
         assert_eq!(4, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
 
         // not less than
         let mut computer = setup_computer(vec![1107, 1, 0, 4, 1]);
         let status = computer
-            .perform_operation(Operation::LessThan(
+            .perform_test_operation(Operation::LessThan(
                 ParameterMode::Immediate,
                 ParameterMode::Immediate,
+                ParameterMode::Position,
             ))
             .expect("Failed to execute operation");
-        assert_eq!(vec![1107, 1, 0, 4, 0], computer.memory);
+        assert_eq!(vec![1107, 1, 0, 4, 0], computer.memory.to_vec());
         assert_eq!(4, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
     }
@@ -662,24 +1529,26 @@ mod tests {
         // equal to
         let mut computer = setup_computer(vec![8, 4, 5, 6, 0, 0, 0]);
         let status = computer
-            .perform_operation(Operation::EqualTo(
+            .perform_test_operation(Operation::EqualTo(
+                ParameterMode::Position,
                 ParameterMode::Position,
                 ParameterMode::Position,
             ))
             .expect("Failed to execute operation");
-        assert_eq!(vec![8, 4, 5, 6, 0, 0, 1], computer.memory);
+        assert_eq!(vec![8, 4, 5, 6, 0, 0, 1], computer.memory.to_vec());
         assert_eq!(4, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
 
         // not equal to
         let mut computer = setup_computer(vec![8, 4, 5, 6, 1, 0, 1]);
         let status = computer
-            .perform_operation(Operation::EqualTo(
+            .perform_test_operation(Operation::EqualTo(
+                ParameterMode::Position,
                 ParameterMode::Position,
                 ParameterMode::Position,
             ))
             .expect("Failed to execute operation");
-        assert_eq!(vec![8, 4, 5, 6, 1, 0, 0], computer.memory);
+        assert_eq!(vec![8, 4, 5, 6, 1, 0, 0], computer.memory.to_vec());
         assert_eq!(4, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
     }
@@ -689,70 +1558,178 @@ mod tests {
         // equal to
         let mut computer = setup_computer(vec![1108, 0, 0, 4, 0]);
         let status = computer
-            .perform_operation(Operation::EqualTo(
+            .perform_test_operation(Operation::EqualTo(
                 ParameterMode::Immediate,
                 ParameterMode::Immediate,
+                ParameterMode::Position,
             ))
             .expect("Failed to execute operation");
-        assert_eq!(vec![1108, 0, 0, 4, 1], computer.memory);
+        assert_eq!(vec![1108, 0, 0, 4, 1], computer.memory.to_vec());
         assert_eq!(4, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
 
         // not equal to
         let mut computer = setup_computer(vec![1108, 1, 0, 4, 1]);
         let status = computer
-            .perform_operation(Operation::EqualTo(
+            .perform_test_operation(Operation::EqualTo(
                 ParameterMode::Immediate,
                 ParameterMode::Immediate,
+                ParameterMode::Position,
             ))
             .expect("Failed to execute operation");
-        assert_eq!(vec![1108, 1, 0, 4, 0], computer.memory);
+        assert_eq!(vec![1108, 1, 0, 4, 0], computer.memory.to_vec());
         assert_eq!(4, computer.program_counter);
         assert_eq!(ExecutionStatus::Ongoing, status);
     }
 
+    #[test]
+    fn it_should_adjust_the_relative_base() {
+        let mut computer = setup_computer(vec![109, 19]);
+        computer.relative_base = 2000;
+        let status = computer
+            .perform_test_operation(Operation::AdjustRelativeBase(ParameterMode::Immediate))
+            .expect("Failed to execute operation");
+        assert_eq!(2019, computer.relative_base);
+        assert_eq!(2, computer.program_counter);
+        assert_eq!(ExecutionStatus::Ongoing, status);
+
+        let mut computer = setup_computer(vec![209, 1, -7]);
+        computer.relative_base = 1;
+        let status = computer
+            .perform_test_operation(Operation::AdjustRelativeBase(ParameterMode::Relative))
+            .expect("Failed to execute operation");
+        assert_eq!(-6, computer.relative_base);
+        assert_eq!(2, computer.program_counter);
+        assert_eq!(ExecutionStatus::Ongoing, status);
+    }
+
+    #[test]
+    fn it_should_perform_an_addition_in_relative_mode() {
+        let mut computer = setup_computer(vec![22201, 0, 1, 2, 7, 8, 0]);
+        computer.relative_base = 4;
+        let status = computer
+            .perform_test_operation(Operation::Add(
+                ParameterMode::Relative,
+                ParameterMode::Relative,
+                ParameterMode::Relative,
+            ))
+            .expect("Failed to execute operation");
+        assert_eq!(vec![22201, 0, 1, 2, 7, 8, 15], computer.memory.to_vec());
+        assert_eq!(4, computer.program_counter);
+        assert_eq!(ExecutionStatus::Ongoing, status);
+    }
+
+    #[test]
+    fn it_should_retrieve_input_in_relative_mode() {
+        let mut computer = IntcodeComputer {
+            program_counter: 0,
+            relative_base: 5,
+            memory: Memory::new(vec![203, -2, 0, 0]),
+            input: vec![5].into(),
+            output: VecDeque::new(),
+            halted: false,
+            steps: 0,
+            tracer: None,
+            journal: None,
+            step_limit: None,
+            cycle_detector: None,
+            profile: None,
+            code_tracker: None,
+            decode_cache: None,
+        };
+        let status = computer
+            .perform_test_operation(Operation::StoreInput(ParameterMode::Relative))
+            .expect("Failed to execute operation");
+        assert_eq!(vec![203, -2, 0, 5], computer.memory.to_vec());
+        assert_eq!(2, computer.program_counter);
+        assert_eq!(ExecutionStatus::Ongoing, status);
+    }
+
+    #[test]
+    fn it_should_fail_for_negative_relative_addresses() {
+        let mut computer = setup_computer(vec![204, -1]);
+        let failure = computer
+            .perform_test_operation(Operation::ProduceOutput(ParameterMode::Relative))
+            .expect_err("Failed to fail operation");
+        assert_eq!(0, computer.program_counter);
+        assert_eq!(
+            ExecutionError::IndexOutsideOfProgram {
+                index: -1,
+                program_length: 2
+            },
+            failure
+        );
+    }
+
     #[test]
     fn it_should_terminate_a_program() {
         let mut computer = setup_computer(vec![99]);
         let status = computer
-            .perform_operation(Operation::Terminate)
+            .perform_test_operation(Operation::Terminate)
             .expect("Failed to execute operation");
-        assert_eq!(vec![99], computer.memory);
+        assert_eq!(vec![99], computer.memory.to_vec());
         assert_eq!(1, computer.program_counter);
         assert_eq!(ExecutionStatus::Terminated, status);
     }
 
+    #[test]
+    fn it_should_read_and_write_outside_of_a_program() {
+        let mut computer = setup_computer(vec![1, 5, 2, 6]);
+        let status = computer
+            .perform_test_operation(Operation::Add(
+                ParameterMode::Position,
+                ParameterMode::Immediate,
+                ParameterMode::Position,
+            ))
+            .expect("Failed to execute operation");
+        assert_eq!(vec![1, 5, 2, 6, 0, 0, 2], computer.memory.to_vec());
+        assert_eq!(4, computer.program_counter);
+        assert_eq!(ExecutionStatus::Ongoing, status);
+
+        let mut computer = setup_computer(vec![1101, 5, 2, 1_000_000]);
+        computer
+            .perform_test_operation(Operation::Add(
+                ParameterMode::Immediate,
+                ParameterMode::Immediate,
+                ParameterMode::Position,
+            ))
+            .expect("Failed to execute operation");
+        assert_eq!(&7, computer.memory.get(1_000_000));
+        assert_eq!(&0, computer.memory.get(999_999));
+    }
+
     #[test]
     fn it_should_fail_for_indexing_outside_of_a_program() {
-        let mut computer = setup_computer(vec![1, 5, 2, 3]);
+        let mut computer = setup_computer(vec![1, -5, 2, 3]);
         let failure = computer
-            .perform_operation(Operation::Add(
+            .perform_test_operation(Operation::Add(
                 ParameterMode::Position,
                 ParameterMode::Immediate,
+                ParameterMode::Position,
             ))
             .expect_err("Failed to fail operation");
-        assert_eq!(vec![1, 5, 2, 3], computer.memory);
+        assert_eq!(vec![1, -5, 2, 3], computer.memory.to_vec());
         assert_eq!(0, computer.program_counter);
         assert_eq!(
             ExecutionError::IndexOutsideOfProgram {
-                index: 5,
+                index: -5,
                 program_length: 4
             },
             failure
         );
 
-        let mut computer = setup_computer(vec![1, -5, 2, 3]);
+        let mut computer = setup_computer(vec![1101, 5, 2, -3]);
         let failure = computer
-            .perform_operation(Operation::Add(
-                ParameterMode::Position,
+            .perform_test_operation(Operation::Add(
+                ParameterMode::Immediate,
                 ParameterMode::Immediate,
+                ParameterMode::Position,
             ))
             .expect_err("Failed to fail operation");
-        assert_eq!(vec![1, -5, 2, 3], computer.memory);
-        assert_eq!(0, computer.program_counter);
+        assert_eq!(vec![1101, 5, 2, -3], computer.memory.to_vec());
         assert_eq!(
             ExecutionError::IndexOutsideOfProgram {
-                index: -5,
+                index: -3,
                 program_length: 4
             },
             failure
@@ -761,35 +1738,192 @@ mod tests {
 
     #[test]
     fn it_should_fail_for_invalid_operation_index() {
+        let mut computer = setup_computer(vec![1105, 1, -1]);
+        let failure = computer.execute().expect_err("Failed to fail operation");
+        assert_eq!(0, computer.program_counter);
+        assert_eq!(ExecutionError::InvalidOperationIndex { index: -1 }, failure);
+    }
+
+    #[test]
+    fn it_should_fail_for_running_past_the_end_of_a_program() {
         let mut computer = setup_computer(vec![1, 0, 0, 3]);
         let failure = computer.execute().expect_err("Failed to fail operation");
-        assert_eq!(vec![1, 0, 0, 2], computer.memory);
+        assert_eq!(vec![1, 0, 0, 2], computer.memory.to_vec());
         assert_eq!(4, computer.program_counter);
-        assert_eq!(ExecutionError::InvalidOperationIndex { index: 4 }, failure);
+        assert_eq!(
+            ExecutionError::InvalidOperationCode { index: 4, code: 0 },
+            failure
+        );
     }
 
     #[test]
-    fn it_should_fail_for_invalid_request_for_input() {
+    fn it_should_await_unavailable_input() {
         let mut computer = IntcodeComputer {
             program_counter: 0,
-            memory: vec![3, 3, 0, 0],
+            relative_base: 0,
+            memory: Memory::new(vec![3, 3, 0, 0]),
             input: VecDeque::new(),
             output: VecDeque::new(),
+            halted: false,
+            steps: 0,
+            tracer: None,
+            journal: None,
+            step_limit: None,
+            cycle_detector: None,
+            profile: None,
+            code_tracker: None,
+            decode_cache: None,
         };
-        let failure = computer
-            .perform_operation(Operation::StoreInput)
-            .expect_err("Failed to execute operation");
-        assert_eq!(vec![3, 3, 0, 0], computer.memory);
+        let status = computer
+            .perform_test_operation(Operation::StoreInput(ParameterMode::Position))
+            .expect("Failed to execute operation");
+        assert_eq!(vec![3, 3, 0, 0], computer.memory.to_vec());
         assert_eq!(VecDeque::new(), computer.input);
         assert_eq!(0, computer.program_counter);
+        assert_eq!(ExecutionStatus::AwaitingInput, status);
+    }
+
+    #[test]
+    fn it_should_fail_for_invalid_request_for_input() {
+        let mut computer = setup_computer(vec![3, 3, 99, 0]);
+        let failure = computer
+            .run_to_halt()
+            .expect_err("Failed to fail execution");
+        assert_eq!(0, computer.program_counter);
         assert_eq!(ExecutionError::InvalidRequestForInput { index: 0 }, failure);
     }
 
+    #[test]
+    fn it_should_resume_execution_when_input_is_provided() {
+        let mut computer = setup_computer(vec![3, 12, 3, 13, 1, 12, 13, 14, 4, 14, 99]);
+        assert_eq!(Ok(ExecutionState::AwaitingInput), computer.execute());
+        assert_eq!(0, computer.program_counter);
+
+        computer.push_input(3);
+        assert_eq!(Ok(ExecutionState::AwaitingInput), computer.execute());
+        assert_eq!(2, computer.program_counter);
+
+        computer.push_input(4);
+        assert_eq!(Ok(ExecutionState::Halted), computer.execute());
+        assert!(computer.is_halted());
+        assert_eq!(VecDeque::from(vec![7]), computer.take_output());
+
+        assert_eq!(Ok(ExecutionState::Halted), computer.execute());
+        assert_eq!(VecDeque::new(), computer.take_output());
+    }
+
+    #[test]
+    fn it_should_pause_when_output_is_produced() {
+        let mut computer = setup_computer(vec![104, 1, 104, 2, 99]);
+        assert_eq!(
+            Ok(ExecutionState::ProducedOutput(1)),
+            computer.execute_until_output()
+        );
+        assert_eq!(2, computer.program_counter);
+        assert_eq!(
+            Ok(ExecutionState::ProducedOutput(2)),
+            computer.execute_until_output()
+        );
+        assert_eq!(Ok(ExecutionState::Halted), computer.execute_until_output());
+        assert_eq!(VecDeque::new(), computer.take_output());
+    }
+
+    #[test]
+    fn it_should_step_back_through_the_journal() {
+        let program = vec![3, 15, 109, 5, 1002, 15, 2, 15, 204, 10, 99, 0, 0, 0, 0, 0];
+        let mut computer = setup_computer(program.clone());
+        computer.enable_journal(10);
+        computer.push_input(4);
+        assert_eq!(Ok(ExecutionState::Halted), computer.execute());
+        assert_eq!(5, computer.steps());
+
+        let entry = computer.step_back().expect("Failed to step back");
+        assert!(entry.halted);
+        assert!(!computer.is_halted());
+        assert_eq!(10, computer.program_counter());
+        let entry = computer.step_back().expect("Failed to step back");
+        assert_eq!(Some(8), entry.output);
+        assert_eq!(VecDeque::new(), computer.output);
+
+        assert!(computer.rewind_to(0));
+        assert_eq!(program, computer.memory.to_vec());
+        assert_eq!(0, computer.relative_base);
+        assert_eq!(VecDeque::from(vec![4]), computer.input);
+        assert_eq!(0, computer.program_counter());
+        assert_eq!(None, computer.step_back());
+
+        assert_eq!(Ok(ExecutionState::Halted), computer.execute());
+        assert_eq!(VecDeque::from(vec![8]), computer.take_output());
+        assert!(!computer.rewind_to(6));
+        assert!(computer.rewind_to(3));
+        assert_eq!(VecDeque::new(), computer.output);
+        assert_eq!(&8, computer.read_memory(15));
+    }
+
+    #[test]
+    fn it_should_only_keep_a_bounded_journal() {
+        let mut computer = setup_computer(vec![1001, 9, 1, 9, 1001, 9, 1, 9, 99, 0]);
+        computer.enable_journal(2);
+        assert_eq!(Ok(ExecutionState::Halted), computer.execute());
+        assert_eq!(Some(1), computer.earliest_step());
+        assert!(!computer.rewind_to(0));
+        assert_eq!(&2, computer.read_memory(9));
+        assert!(computer.rewind_to(1));
+        assert_eq!(&1, computer.read_memory(9));
+
+        computer.write_memory(9, 5);
+        assert_eq!(None, computer.earliest_step());
+        assert!(computer.rewind_to(1));
+        assert!(!computer.rewind_to(0));
+    }
+
+    #[test]
+    fn it_should_not_step_back_across_edited_input() {
+        let mut computer = setup_computer(vec![3, 9, 3, 10, 4, 9, 4, 10, 99, 0, 0]);
+        computer.enable_journal(10);
+        computer.push_input(1);
+        computer.push_input(2);
+        assert!(matches!(computer.step(), Ok(StepResult::Executed(_))));
+        assert_eq!(VecDeque::from(vec![2]), computer.input);
+
+        computer.input_queue_mut().clear();
+        computer.input_queue_mut().push_back(7);
+        assert_eq!(None, computer.step_back());
+        assert!(!computer.rewind_to(0));
+        assert_eq!(VecDeque::from(vec![7]), computer.input);
+
+        assert_eq!(Ok(ExecutionState::Halted), computer.execute());
+        assert_eq!(VecDeque::from(vec![1, 7]), computer.take_output());
+        assert!(computer.rewind_to(1));
+        assert_eq!(VecDeque::from(vec![7]), computer.input);
+    }
+
+    #[test]
+    fn it_should_allow_the_state_to_be_inspected_and_edited() {
+        let mut computer = setup_computer(vec![3, 7, 204, 0, 99]);
+        computer.input_queue_mut().push_back(5);
+        assert_eq!(&VecDeque::from(vec![5]), computer.input_queue());
+
+        computer.write_memory(1, 10);
+        computer.set_relative_base(10);
+        assert_eq!(Ok(ExecutionState::Halted), computer.execute());
+        assert_eq!(&5, computer.read_memory(10));
+        assert_eq!(&10, computer.relative_base());
+        assert_eq!(11, computer.memory_len());
+        assert_eq!(VecDeque::from(vec![5]), computer.take_output());
+
+        computer.set_program_counter(2);
+        assert!(!computer.is_halted());
+        assert_eq!(2, computer.program_counter());
+        assert_eq!(Ok(ExecutionState::Halted), computer.execute());
+        assert_eq!(VecDeque::from(vec![5]), computer.take_output());
+    }
+
     #[test]
     fn it_should_fail_for_negative_operation_codes() {
         let mut computer = setup_computer(vec![-1, 0, 0, 3]);
         let failure = computer.execute().expect_err("Failed to fail operation");
-        assert_eq!(vec![-1, 0, 0, 3], computer.memory);
+        assert_eq!(vec![-1, 0, 0, 3], computer.memory.to_vec());
         assert_eq!(0, computer.program_counter);
         assert_eq!(
             ExecutionError::InvalidOperationCode { index: 0, code: -1 },
@@ -800,10 +1934,299 @@ mod tests {
     #[test]
     fn it_should_execute_an_entire_program() {
         let mut computer = setup_computer(vec![1, 0, 0, 3, 4, 3, 99]);
-        let output = computer.execute().expect("Failed to execute program");
+        let output = computer.run_to_halt().expect("Failed to execute program");
         assert_eq!(VecDeque::from(vec![2]), output);
     }
 
+    #[test]
+    fn it_should_execute_a_program_using_the_relative_base() {
+        let mut computer = setup_computer(vec![109, 7, 204, -1, 99, 0, 42, 1337]);
+        let output = computer.run_to_halt().expect("Failed to execute program");
+        assert_eq!(VecDeque::from(vec![42]), output);
+    }
+
+    #[test]
+    fn it_should_describe_each_step() {
+        let mut computer = setup_computer(vec![3, 11, 1001, 11, 5, 12, 1005, 12, 10, 99, 204, -12]);
+        computer.push_input(7);
+
+        assert_eq!(
+            Ok(StepResult::Executed(ExecutionEvent {
+                address: 0,
+                operation: Operation::StoreInput(ParameterMode::Position),
+                operands: vec![11],
+                memory_write: Some(MemoryWrite {
+                    address: 11,
+                    previous: -12,
+                    value: 7
+                }),
+                input: Some(7),
+                output: None,
+                program_counter: 2,
+            })),
+            computer.step()
+        );
+        assert_eq!(
+            Ok(StepResult::Executed(ExecutionEvent {
+                address: 2,
+                operation: Operation::Add(
+                    ParameterMode::Position,
+                    ParameterMode::Immediate,
+                    ParameterMode::Position
+                ),
+                operands: vec![7, 5, 12],
+                memory_write: Some(MemoryWrite {
+                    address: 12,
+                    previous: 0,
+                    value: 12
+                }),
+                input: None,
+                output: None,
+                program_counter: 6,
+            })),
+            computer.step()
+        );
+        assert_eq!(
+            Ok(StepResult::Executed(ExecutionEvent {
+                address: 6,
+                operation: Operation::JumpIfTrue(ParameterMode::Position, ParameterMode::Immediate),
+                operands: vec![12, 10],
+                memory_write: None,
+                input: None,
+                output: None,
+                program_counter: 10,
+            })),
+            computer.step()
+        );
+        assert_eq!(
+            Ok(StepResult::Executed(ExecutionEvent {
+                address: 10,
+                operation: Operation::ProduceOutput(ParameterMode::Relative),
+                operands: vec![12],
+                memory_write: None,
+                input: None,
+                output: Some(12),
+                program_counter: 12,
+            })),
+            computer.step()
+        );
+    }
+
+    #[test]
+    fn it_should_step_until_halted() {
+        let mut computer = setup_computer(vec![3, 3, 99, 0]);
+        assert_eq!(Ok(StepResult::AwaitingInput), computer.step());
+        assert_eq!(0, computer.program_counter);
+
+        computer.push_input(1);
+        assert!(matches!(computer.step(), Ok(StepResult::Executed(_))));
+        assert_eq!(
+            Ok(StepResult::Executed(ExecutionEvent {
+                address: 2,
+                operation: Operation::Terminate,
+                operands: vec![],
+                memory_write: None,
+                input: None,
+                output: None,
+                program_counter: 3,
+            })),
+            computer.step()
+        );
+        assert_eq!(Ok(StepResult::Halted), computer.step());
+    }
+
+    #[test]
+    fn it_should_run_with_an_input_source_and_output_sink() {
+        let doubler = vec![3, 9, 102, 2, 9, 9, 4, 9, 1105, 1, 0];
+
+        let mut computer = setup_computer(doubler.clone());
+        let mut output = Vec::new();
+        let state = computer
+            .run_with(&mut IterInput(vec![1, 2, 3].into_iter()), &mut output)
+            .expect("Failed to execute program");
+        assert_eq!(ExecutionState::AwaitingInput, state);
+        assert_eq!(vec![2, 4, 6], output);
+        assert_eq!(VecDeque::new(), computer.take_output());
+
+        let outputs = Rc::new(RefCell::new(vec![1]));
+        let mut input = FnInput(|| {
+            let last = *outputs.borrow().last()?;
+            if last < 100 {
+                Some(last + 1)
+            } else {
+                None
+            }
+        });
+        let mut output = FnOutput(|value| outputs.borrow_mut().push(value));
+        let mut computer = setup_computer(doubler);
+        let state = computer
+            .run_with(&mut input, &mut output)
+            .expect("Failed to execute program");
+        assert_eq!(ExecutionState::AwaitingInput, state);
+        assert_eq!(vec![1, 4, 10, 22, 46, 94, 190], *outputs.borrow());
+    }
+
+    #[test]
+    fn it_should_report_device_failures() {
+        let mut computer = setup_computer(vec![3, 5, 4, 5, 99, 0]);
+        let failure = computer
+            .run_with(
+                &mut ReaderInput::new("seven".as_bytes()),
+                &mut VecDeque::new(),
+            )
+            .expect_err("Failed to fail execution");
+        assert_eq!(
+            ExecutionError::DeviceFailure {
+                index: 0,
+                reason: String::from("Unable to parse input value \"seven\"")
+            },
+            failure
+        );
+
+        let mut computer = setup_computer(vec![3, 5, 4, 5, 99, 0]);
+        let mut output = WriterOutput(Vec::new());
+        let state = computer
+            .run_with(&mut ReaderInput::new("7\n".as_bytes()), &mut output)
+            .expect("Failed to execute program");
+        assert_eq!(ExecutionState::Halted, state);
+        assert_eq!(b"7\n".to_vec(), output.0);
+    }
+
+    #[test]
+    fn it_should_run_code_far_past_the_image_with_the_decode_cache() {
+        let mut computer = setup_computer(vec![1105, 1, 1_000_000]);
+        computer.enable_decode_cache();
+        computer.write_memory(1_000_000, 104);
+        computer.write_memory(1_000_001, 5);
+        computer.write_memory(1_000_002, 99);
+        assert_eq!(Ok(ExecutionState::Halted), computer.execute());
+        assert_eq!(VecDeque::from(vec![5]), computer.take_output());
+    }
+
+    #[test]
+    fn it_should_decode_patched_instructions_again() {
+        // increments the parameter of its output instruction after every output
+        let program = vec![104, 7, 1001, 1, 1, 1, 1105, 1, 0];
+        let mut cached = IntcodeComputer::new(program.clone());
+        cached.enable_decode_cache();
+        let mut predecoded = IntcodeComputer::new(program.clone());
+        predecoded.predecode();
+        let computers = vec![
+            IntcodeComputer::new(program),
+            cached,
+            predecoded.clone(),
+            // clones only see their own writes
+            predecoded.clone(),
+        ];
+        for mut computer in computers {
+            let outputs: Vec<_> = (0..3).map(|_| computer.execute_until_output()).collect();
+            assert_eq!(
+                vec![
+                    Ok(ExecutionState::ProducedOutput(7)),
+                    Ok(ExecutionState::ProducedOutput(8)),
+                    Ok(ExecutionState::ProducedOutput(9))
+                ],
+                outputs
+            );
+
+            // editing memory by hand turns the output into one from position 10
+            computer.write_memory(0, 4);
+            assert_eq!(
+                Ok(ExecutionState::ProducedOutput(0)),
+                computer.execute_until_output()
+            );
+        }
+    }
+
+    #[test]
+    fn it_should_stop_at_the_step_limit() {
+        // counts upwards forever
+        let mut computer = IntcodeComputer::new(vec![1001, 7, 1, 7, 1105, 1, 0, 0]);
+        computer.set_step_limit(Some(5));
+        assert_eq!(
+            Err(ExecutionError::StepLimitExceeded { steps: 5, index: 4 }),
+            computer.execute()
+        );
+        assert_eq!(&3, computer.read_memory(7));
+
+        computer.set_step_limit(Some(8));
+        assert_eq!(
+            Err(ExecutionError::StepLimitExceeded { steps: 8, index: 0 }),
+            computer.execute()
+        );
+        assert_eq!(&4, computer.read_memory(7));
+    }
+
+    #[test]
+    fn it_should_detect_infinite_loops() {
+        // toggles address 9 between zero and one forever
+        let mut computer = IntcodeComputer::new(vec![1008, 9, 0, 9, 1105, 1, 0, 99, 0, 0]);
+        computer.enable_cycle_detection();
+        assert_eq!(
+            Err(ExecutionError::InfiniteLoop {
+                index: 0,
+                step: 4,
+                previous_step: 0
+            }),
+            computer.execute()
+        );
+
+        // a loop that keeps producing output is left to the step limit
+        let mut computer = IntcodeComputer::new(vec![4, 0, 1105, 1, 0]);
+        computer.enable_cycle_detection();
+        computer.set_step_limit(Some(100));
+        assert_eq!(
+            Err(ExecutionError::StepLimitExceeded {
+                steps: 100,
+                index: 0
+            }),
+            computer.execute()
+        );
+
+        // neither is waiting for input, or going back to a state after changing memory by hand
+        let mut computer = IntcodeComputer::new(vec![3, 0, 1105, 1, 0]);
+        computer.enable_cycle_detection();
+        assert_eq!(Ok(ExecutionState::AwaitingInput), computer.execute());
+        assert_eq!(Ok(ExecutionState::AwaitingInput), computer.execute());
+        computer.push_input(3);
+        computer.write_memory(0, 3);
+        assert_eq!(Ok(ExecutionState::AwaitingInput), computer.execute());
+    }
+
+    #[test]
+    fn it_should_execute_programs_with_large_numbers() {
+        let quine = vec![
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
+        let mut computer = setup_computer(quine.clone());
+        let output = computer.run_to_halt().expect("Failed to execute program");
+        assert_eq!(VecDeque::from(quine), output);
+
+        let mut computer = setup_computer(vec![1102, 34_915_192, 34_915_192, 7, 4, 7, 99, 0]);
+        let output = computer.run_to_halt().expect("Failed to execute program");
+        assert_eq!(VecDeque::from(vec![1_219_070_632_396_864]), output);
+
+        let mut computer = setup_computer(vec![104, 1_125_899_906_842_624, 99]);
+        let output = computer.run_to_halt().expect("Failed to execute program");
+        assert_eq!(VecDeque::from(vec![1_125_899_906_842_624]), output);
+    }
+
+    #[test]
+    fn it_should_execute_programs_with_wider_words() {
+        let program = vec![1102, 4_611_686_018_427_387_904, 4, 7, 4, 7, 99, 0];
+
+        let mut computer: IntcodeComputer<i128> = IntcodeComputer::new(program.clone());
+        let output = computer.run_to_halt().expect("Failed to execute program");
+        assert_eq!(VecDeque::from(vec![18_446_744_073_709_551_616]), output);
+
+        let mut computer = IntcodeComputer::new(program.into_iter().map(BigInt::from).collect());
+        let output = computer.run_to_halt().expect("Failed to execute program");
+        assert_eq!(
+            VecDeque::from(vec![BigInt::from(18_446_744_073_709_551_616_i128)]),
+            output
+        );
+    }
+
     #[test]
     fn it_should_test_the_puzzle_examples() {
         // Here are several programs that take one input, compare it to the value 8, and then produce one output
@@ -831,7 +2254,7 @@ mod tests {
             ],
             VecDeque::from(vec![7]),
         );
-        let output = computer.execute().expect("Failed to execute program");
+        let output = computer.run_to_halt().expect("Failed to execute program");
         assert_eq!(VecDeque::from(vec![999]), output);
 
         let mut computer = IntcodeComputer::new_with_input(
@@ -842,7 +2265,7 @@ mod tests {
             ],
             VecDeque::from(vec![8]),
         );
-        let output = computer.execute().expect("Failed to execute program");
+        let output = computer.run_to_halt().expect("Failed to execute program");
         assert_eq!(VecDeque::from(vec![1000]), output);
 
         let mut computer = IntcodeComputer::new_with_input(
@@ -853,7 +2276,7 @@ mod tests {
             ],
             VecDeque::from(vec![9]),
         );
-        let output = computer.execute().expect("Failed to execute program");
+        let output = computer.run_to_halt().expect("Failed to execute program");
         assert_eq!(VecDeque::from(vec![1001]), output);
     }
 }
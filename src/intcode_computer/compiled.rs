@@ -0,0 +1,432 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use super::decode_cache::{DecodedInstruction, Operand};
+use super::memory::Memory;
+use super::{ExecutionError, ExecutionState, IntcodeComputer, Operation, StepResult, Word};
+
+/// What happened when an instruction executed, beyond moving the program counter
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Flow {
+    Continue,
+    Wrote(usize),
+    Output,
+    AwaitingInput,
+    Halted,
+}
+
+type Compiled<W> =
+    Box<dyn Fn(&mut IntcodeComputer<W>) -> Result<Flow, ExecutionError<W>> + Send + Sync>;
+
+struct CompiledInstruction<W: Word> {
+    length: usize,
+    run: Compiled<W>,
+}
+
+/// A program image compiled ahead of time into a closure for every address that holds a valid
+/// instruction, with the operation and its operands already bound. Every machine that runs the
+/// program shares the closures, and only falls back to the interpreter at addresses that it has
+/// written over since, or that did not hold an instruction when the program was compiled.
+pub struct CompiledProgram<W: Word = i64> {
+    image: Vec<W>,
+    instructions: Vec<Option<CompiledInstruction<W>>>,
+}
+
+impl<W: Word + Send + Sync + 'static> CompiledProgram<W> {
+    pub fn compile(program: &[W]) -> Self {
+        let memory = Memory::new(program.to_vec());
+        let instructions = (0..program.len())
+            .map(|address| {
+                let instruction = DecodedInstruction::decode(&memory, address).ok()?;
+                Some(CompiledInstruction {
+                    length: instruction.length(),
+                    run: compile_instruction(address, instruction),
+                })
+            })
+            .collect();
+        CompiledProgram {
+            image: program.to_vec(),
+            instructions,
+        }
+    }
+
+    /// A new machine that runs the program from the start
+    pub fn instantiate(self: &Arc<Self>) -> CompiledComputer<W> {
+        CompiledComputer {
+            program: Arc::clone(self),
+            computer: IntcodeComputer::new(self.image.clone()),
+            stale: BTreeSet::new(),
+            shadow: None,
+        }
+    }
+}
+
+fn compile_instruction<W: Word + Send + Sync + 'static>(
+    address: usize,
+    instruction: DecodedInstruction<W>,
+) -> Compiled<W> {
+    let next = address + instruction.length();
+    let [first, second, third] = instruction.operands;
+    match instruction.operation {
+        Operation::Add(_, _, _) => binary(first, second, third, next, |left, right| left + right),
+        Operation::Multiply(_, _, _) => {
+            binary(first, second, third, next, |left, right| left * right)
+        }
+        Operation::LessThan(_, _, _) => binary(first, second, third, next, |left, right| {
+            if left < right {
+                W::one()
+            } else {
+                W::zero()
+            }
+        }),
+        Operation::EqualTo(_, _, _) => binary(first, second, third, next, |left, right| {
+            if left == right {
+                W::one()
+            } else {
+                W::zero()
+            }
+        }),
+        Operation::StoreInput(_) => Box::new(move |computer| {
+            let input = match computer.input.front() {
+                Some(input) => input.clone(),
+                None => return Ok(Flow::AwaitingInput),
+            };
+            let index = computer.fetch_output_index(&first);
+            let written = write(computer, index, input)?;
+            computer.input.pop_front();
+            computer.program_counter = next;
+            Ok(Flow::Wrote(written))
+        }),
+        Operation::ProduceOutput(_) => Box::new(move |computer| {
+            let output = computer.fetch_parameter(&first)?;
+            computer.output.push_back(output);
+            computer.program_counter = next;
+            Ok(Flow::Output)
+        }),
+        Operation::JumpIfTrue(_, _) => jump(first, second, next, |value| !value.is_zero()),
+        Operation::JumpIfFalse(_, _) => jump(first, second, next, |value| value.is_zero()),
+        Operation::AdjustRelativeBase(_) => Box::new(move |computer| {
+            let adjustment = computer.fetch_parameter(&first)?;
+            computer.relative_base = computer.relative_base.clone() + adjustment;
+            computer.program_counter = next;
+            Ok(Flow::Continue)
+        }),
+        Operation::Terminate => Box::new(move |computer| {
+            computer.program_counter = next;
+            computer.halted = true;
+            Ok(Flow::Halted)
+        }),
+    }
+}
+
+fn binary<W: Word + Send + Sync + 'static>(
+    left: Operand<W>,
+    right: Operand<W>,
+    output: Operand<W>,
+    next: usize,
+    operation: impl Fn(W, W) -> W + Send + Sync + 'static,
+) -> Compiled<W> {
+    Box::new(move |computer| {
+        let left = computer.fetch_parameter(&left)?;
+        let right = computer.fetch_parameter(&right)?;
+        let index = computer.fetch_output_index(&output);
+        let written = write(computer, index, operation(left, right))?;
+        computer.program_counter = next;
+        Ok(Flow::Wrote(written))
+    })
+}
+
+fn jump<W: Word + Send + Sync + 'static>(
+    condition: Operand<W>,
+    target: Operand<W>,
+    next: usize,
+    taken: impl Fn(&W) -> bool + Send + Sync + 'static,
+) -> Compiled<W> {
+    Box::new(move |computer| {
+        if taken(&computer.fetch_parameter(&condition)?) {
+            let target = computer.fetch_parameter(&target)?;
+            computer.program_counter = target
+                .to_usize()
+                .ok_or(ExecutionError::InvalidOperationIndex { index: target })?;
+        } else {
+            computer.program_counter = next;
+        }
+        Ok(Flow::Continue)
+    })
+}
+
+fn write<W: Word>(
+    computer: &mut IntcodeComputer<W>,
+    index: W,
+    value: W,
+) -> Result<usize, ExecutionError<W>> {
+    let address = computer.to_index(index)?;
+    computer.store(address, value);
+    Ok(address)
+}
+
+/// A machine that runs a compiled program, producing exactly the same state as an
+/// `IntcodeComputer` running the same program would after every instruction
+pub struct CompiledComputer<W: Word = i64> {
+    program: Arc<CompiledProgram<W>>,
+    computer: IntcodeComputer<W>,
+    /// The start addresses of compiled instructions that the machine has written over
+    stale: BTreeSet<usize>,
+    shadow: Option<IntcodeComputer<W>>,
+}
+
+impl<W: Word> CompiledComputer<W> {
+    /// Runs an interpreter alongside the compiled code from now on, failing with
+    /// `CompilationMismatch` as soon as an instruction leaves the two in different states. This
+    /// is slow, since it compares all of memory after every instruction, so it is meant for
+    /// testing.
+    pub fn enable_differential_testing(&mut self) {
+        self.shadow = Some(self.computer.clone());
+    }
+
+    /// The machine state, as an interpreter would have it
+    pub fn computer(&self) -> &IntcodeComputer<W> {
+        &self.computer
+    }
+
+    /// Hands the machine over to the interpreter
+    pub fn into_computer(self) -> IntcodeComputer<W> {
+        self.computer
+    }
+
+    pub fn push_input(&mut self, value: W) {
+        if let Some(shadow) = &mut self.shadow {
+            shadow.push_input(value.clone());
+        }
+        self.computer.push_input(value);
+    }
+
+    pub fn take_output(&mut self) -> std::collections::VecDeque<W> {
+        if let Some(shadow) = &mut self.shadow {
+            shadow.take_output();
+        }
+        self.computer.take_output()
+    }
+
+    pub fn write_memory(&mut self, address: usize, value: W) {
+        if let Some(shadow) = &mut self.shadow {
+            shadow.write_memory(address, value.clone());
+        }
+        self.computer.write_memory(address, value);
+        self.invalidate(address);
+    }
+
+    /// Executes until the program halts or requests input that is not available yet
+    pub fn execute(&mut self) -> Result<ExecutionState<W>, ExecutionError<W>> {
+        self.run(false)
+    }
+
+    /// Executes until the program halts, requests input that is not available yet, or produces
+    /// output, which is returned instead of being added to the output queue
+    pub fn execute_until_output(&mut self) -> Result<ExecutionState<W>, ExecutionError<W>> {
+        self.run(true)
+    }
+
+    /// Executes the program to completion, failing if it requests input that was not provided
+    pub fn run_to_halt(&mut self) -> Result<std::collections::VecDeque<W>, ExecutionError<W>> {
+        match self.execute()? {
+            ExecutionState::Halted => Ok(self.take_output()),
+            _ => Err(ExecutionError::InvalidRequestForInput {
+                index: self.computer.program_counter,
+            }),
+        }
+    }
+
+    fn run(&mut self, pause_on_output: bool) -> Result<ExecutionState<W>, ExecutionError<W>> {
+        loop {
+            match self.step()? {
+                Flow::Continue => {}
+                Flow::Wrote(address) => self.invalidate(address),
+                Flow::Output if pause_on_output => {
+                    if let Some(shadow) = &mut self.shadow {
+                        shadow.deliver_output();
+                    }
+                    let output = self
+                        .computer
+                        .deliver_output()
+                        .expect("The output was just produced");
+                    return Ok(ExecutionState::ProducedOutput(output));
+                }
+                Flow::Output => {}
+                Flow::AwaitingInput => return Ok(ExecutionState::AwaitingInput),
+                Flow::Halted => return Ok(ExecutionState::Halted),
+            }
+        }
+    }
+
+    fn step(&mut self) -> Result<Flow, ExecutionError<W>> {
+        let flow = self.step_compiled();
+        if let Some(shadow) = &mut self.shadow {
+            let expected = shadow.step();
+            let matches = match (&flow, &expected) {
+                (Ok(_), Ok(_)) => shadow.snapshot() == self.computer.snapshot(),
+                (Err(error), Err(expected)) => error == expected,
+                _ => false,
+            };
+            if !matches {
+                return Err(ExecutionError::CompilationMismatch {
+                    index: self.computer.program_counter,
+                    step: self.computer.steps,
+                });
+            }
+        }
+        flow
+    }
+
+    fn step_compiled(&mut self) -> Result<Flow, ExecutionError<W>> {
+        let address = self.computer.program_counter;
+        if self.computer.halted {
+            return Ok(Flow::Halted);
+        }
+        let compiled = match self.program.instructions.get(address) {
+            Some(Some(instruction)) if !self.stale.contains(&address) => instruction,
+            _ => return self.step_interpreted(),
+        };
+        let flow = (compiled.run)(&mut self.computer)?;
+        if flow != Flow::AwaitingInput {
+            self.computer.steps += 1;
+        }
+        Ok(flow)
+    }
+
+    fn step_interpreted(&mut self) -> Result<Flow, ExecutionError<W>> {
+        Ok(match self.computer.step()? {
+            StepResult::Executed(event) => match (event.memory_write, event.output) {
+                (Some(write), _) => Flow::Wrote(write.address),
+                (_, Some(_)) => Flow::Output,
+                _ if self.computer.halted => Flow::Halted,
+                _ => Flow::Continue,
+            },
+            StepResult::AwaitingInput => Flow::AwaitingInput,
+            StepResult::Halted => Flow::Halted,
+        })
+    }
+
+    /// Stops using every compiled instruction that covers the address
+    fn invalidate(&mut self, address: usize) {
+        let first = address.saturating_sub(3);
+        for start in first..=address {
+            if let Some(Some(instruction)) = self.program.instructions.get(start) {
+                if start + instruction.length > address {
+                    self.stale.insert(start);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::program::Program;
+
+    fn load(path: &str) -> Arc<CompiledProgram> {
+        let program: Vec<i64> = Program::from_file(path)
+            .expect("Failed to load")
+            .into_words();
+        Arc::new(CompiledProgram::compile(&program))
+    }
+
+    #[test]
+    fn it_should_match_the_interpreter_on_the_day_five_diagnostics() {
+        let program = load("data/day-five-input.txt");
+        for &(input, expected) in &[(1, 15_259_545), (5, 7_616_021)] {
+            let mut computer = program.instantiate();
+            computer.enable_differential_testing();
+            computer.push_input(input);
+            let output = computer.run_to_halt().expect("Failed to run");
+            assert_eq!(Some(&expected), output.back());
+        }
+    }
+
+    #[test]
+    fn it_should_match_the_interpreter_on_the_day_seven_amplifiers() {
+        let program = load("data/day-seven-input.txt");
+        for phase in 0..10 {
+            let mut computer = program.instantiate();
+            computer.enable_differential_testing();
+            computer.push_input(phase);
+            let mut signal = 1;
+            while let Ok(ExecutionState::AwaitingInput) = computer.execute() {
+                computer.push_input(signal);
+                signal += 1;
+            }
+            assert!(computer.computer().is_halted());
+        }
+    }
+
+    #[test]
+    fn it_should_fall_back_to_the_interpreter_for_written_code() {
+        // increments the parameter of its output instruction after every output
+        let program = Arc::new(CompiledProgram::compile(&[
+            104, 7, 1001, 1, 1, 1, 1105, 1, 0,
+        ]));
+        let mut computer = program.instantiate();
+        computer.enable_differential_testing();
+        for expected in 7..10 {
+            assert_eq!(
+                Ok(ExecutionState::ProducedOutput(expected)),
+                computer.execute_until_output()
+            );
+        }
+        assert_eq!(7, computer.computer().steps());
+
+        // turns the output into one from position 10, past the end of the program
+        computer.write_memory(0, 4);
+        assert_eq!(
+            Ok(ExecutionState::ProducedOutput(0)),
+            computer.execute_until_output()
+        );
+    }
+
+    #[test]
+    fn it_should_detect_a_divergence_from_the_interpreter() {
+        // compiled for a program that adds where the machine's memory multiplies
+        let mut computer = CompiledComputer {
+            program: Arc::new(CompiledProgram::compile(&[1101, 2, 3, 5, 99, 0])),
+            computer: IntcodeComputer::new(vec![1102, 2, 3, 5, 99, 0]),
+            stale: BTreeSet::new(),
+            shadow: None,
+        };
+        computer.enable_differential_testing();
+        assert_eq!(
+            Err(ExecutionError::CompilationMismatch { index: 4, step: 1 }),
+            computer.execute()
+        );
+    }
+
+    #[test]
+    fn it_should_fail_like_the_interpreter() {
+        let cases: Vec<(Vec<i64>, ExecutionError)> = vec![
+            (
+                vec![1101, 1, 1, 5, 98],
+                ExecutionError::InvalidOperationCode { index: 4, code: 98 },
+            ),
+            (
+                vec![1, -1, 0, 0, 99],
+                ExecutionError::IndexOutsideOfProgram {
+                    index: -1,
+                    program_length: 5,
+                },
+            ),
+            (
+                vec![1105, 1, -3],
+                ExecutionError::InvalidOperationIndex { index: -3 },
+            ),
+        ];
+        for (program, error) in cases {
+            let mut interpreter = IntcodeComputer::new(program.clone());
+            assert_eq!(Err(error.clone()), interpreter.execute());
+
+            let mut computer = Arc::new(CompiledProgram::compile(&program)).instantiate();
+            computer.enable_differential_testing();
+            assert_eq!(Err(error), computer.execute());
+        }
+    }
+}
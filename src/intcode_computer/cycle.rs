@@ -0,0 +1,61 @@
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// Remembers the hashes of the states that a machine has been in since it last read input or
+/// produced output. Without any I/O a machine is deterministic, so coming back to a state means
+/// that it will go around the same loop forever.
+#[derive(Debug, Clone, Default)]
+pub struct CycleDetector {
+    seen: HashMap<u64, u64>,
+}
+
+impl CycleDetector {
+    pub fn hash_state(state: &impl Hash) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        state.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Records the state as seen on the step, returning the step it was first seen on if it has
+    /// been seen before
+    pub fn visit(&mut self, state: u64, step: u64) -> Option<u64> {
+        match self.seen.entry(state) {
+            Entry::Occupied(entry) => Some(*entry.get()),
+            Entry::Vacant(entry) => {
+                entry.insert(step);
+                None
+            }
+        }
+    }
+
+    pub fn forget(&mut self, state: u64) {
+        self.seen.remove(&state);
+    }
+
+    pub fn clear(&mut self) {
+        self.seen.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_report_repeated_states() {
+        let mut detector = CycleDetector::default();
+        let (first, second) = (
+            CycleDetector::hash_state(&(0, vec![1, 2])),
+            CycleDetector::hash_state(&(2, vec![1, 2])),
+        );
+        assert_eq!(None, detector.visit(first, 0));
+        assert_eq!(None, detector.visit(second, 1));
+        assert_eq!(Some(0), detector.visit(first, 2));
+
+        detector.forget(second);
+        assert_eq!(None, detector.visit(second, 3));
+        detector.clear();
+        assert_eq!(None, detector.visit(first, 4));
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use super::memory::Memory;
+use super::{Operation, ParameterMode, Word};
+
+/// The most cells that a single instruction covers
+const MAX_INSTRUCTION_LENGTH: usize = 4;
+
+/// A parameter of a decoded instruction, resolved as far as it can be without knowing the relative
+/// base or what is in memory when the instruction executes
+#[derive(Debug, PartialEq, Clone)]
+pub enum Operand<W: Word> {
+    Immediate(W),
+    Address(usize),
+    Relative(W),
+    /// A position that can not be an address, which fails the instruction if it is ever used
+    Invalid(W),
+}
+
+/// An instruction along with its operation code as it is in memory, so that executing it again
+/// only has to look it up
+#[derive(Debug, PartialEq, Clone)]
+pub struct DecodedInstruction<W: Word> {
+    pub code: W,
+    pub operation: Operation,
+    pub operands: [Operand<W>; 3],
+}
+
+impl<W: Word> DecodedInstruction<W> {
+    /// Decodes the instruction at the address, failing with the operation code if it is invalid
+    pub fn decode(memory: &Memory<W>, address: usize) -> Result<Self, W> {
+        let code = memory.get(address).clone();
+        match Operation::decode(&code) {
+            Ok(operation) => Ok(Self::new(code, operation, memory, address)),
+            Err(_) => Err(code),
+        }
+    }
+
+    pub fn new(code: W, operation: Operation, memory: &Memory<W>, address: usize) -> Self {
+        let modes = parameter_modes(&operation);
+        let operands = std::array::from_fn(|index| {
+            let mode = match modes[index] {
+                Some(mode) => mode,
+                None => return Operand::Immediate(W::zero()),
+            };
+            let value = memory.get(address + 1 + index).clone();
+            match mode {
+                ParameterMode::Position => match value.to_usize() {
+                    Some(address) => Operand::Address(address),
+                    None => Operand::Invalid(value),
+                },
+                ParameterMode::Relative => Operand::Relative(value),
+                ParameterMode::Immediate => Operand::Immediate(value),
+            }
+        });
+        DecodedInstruction {
+            code,
+            operation,
+            operands,
+        }
+    }
+
+    /// The number of cells that the instruction covers
+    pub fn length(&self) -> usize {
+        1 + self.operation.number_of_parameters()
+    }
+
+    fn covers(&self, start: usize, address: usize) -> bool {
+        start + self.length() > address
+    }
+}
+
+/// The same as `Operation::parameter_modes`, without allocating on every step
+fn parameter_modes(operation: &Operation) -> [Option<ParameterMode>; 3] {
+    match *operation {
+        Operation::Add(a, b, c)
+        | Operation::Multiply(a, b, c)
+        | Operation::LessThan(a, b, c)
+        | Operation::EqualTo(a, b, c) => [Some(a), Some(b), Some(c)],
+        Operation::JumpIfTrue(a, b) | Operation::JumpIfFalse(a, b) => [Some(a), Some(b), None],
+        Operation::StoreInput(a)
+        | Operation::ProduceOutput(a)
+        | Operation::AdjustRelativeBase(a) => [Some(a), None, None],
+        Operation::Terminate => [None, None, None],
+    }
+}
+
+/// Decoded instructions by the address that they start at. Writing to any cell that a cached
+/// instruction covers has to invalidate it, since the next time it executes it may decode
+/// differently.
+///
+/// Instructions are either decoded as they are executed, or all at once up front. The ones decoded
+/// up front are shared between clones, which never change them, but instead keep track of which
+/// ones they have written over themselves. Only the first `length` addresses are cached, so that
+/// jumping far past the program image does not allocate a slot for every address on the way.
+#[derive(Debug, Clone)]
+pub struct DecodeCache<W: Word> {
+    length: usize,
+    predecoded: Arc<Vec<Option<DecodedInstruction<W>>>>,
+    stale: BTreeSet<usize>,
+    instructions: Vec<Option<DecodedInstruction<W>>>,
+}
+
+impl<W: Word> DecodeCache<W> {
+    /// Caches instructions as they are decoded at any of the first `length` addresses
+    pub fn new(length: usize) -> Self {
+        DecodeCache {
+            length,
+            predecoded: Arc::new(Vec::new()),
+            stale: BTreeSet::new(),
+            instructions: Vec::new(),
+        }
+    }
+
+    /// Decodes every address in the first `length` cells of memory, whether it holds code or not
+    pub fn predecode(memory: &Memory<W>, length: usize) -> Self {
+        let predecoded = (0..length)
+            .map(|address| DecodedInstruction::decode(memory, address).ok())
+            .collect();
+        DecodeCache {
+            predecoded: Arc::new(predecoded),
+            ..Self::new(length)
+        }
+    }
+
+    pub fn get(&self, address: usize) -> Option<&DecodedInstruction<W>> {
+        match self.instructions.get(address) {
+            Some(Some(instruction)) => Some(instruction),
+            _ if self.stale.contains(&address) => None,
+            _ => self.predecoded.get(address)?.as_ref(),
+        }
+    }
+
+    /// Caches the instruction, unless its address is past the ones that this cache covers
+    pub fn insert(&mut self, address: usize, instruction: DecodedInstruction<W>) {
+        if address >= self.length {
+            return;
+        }
+        if self.instructions.len() <= address {
+            self.instructions.resize(address + 1, None);
+        }
+        self.instructions[address] = Some(instruction);
+    }
+
+    /// Drops every cached instruction that covers the address
+    pub fn invalidate(&mut self, address: usize) {
+        let first = address.saturating_sub(MAX_INSTRUCTION_LENGTH - 1);
+        for start in first..=address {
+            if let Some(cached) = self.instructions.get_mut(start) {
+                if cached
+                    .as_ref()
+                    .is_some_and(|instruction| instruction.covers(start, address))
+                {
+                    *cached = None;
+                }
+            }
+            if let Some(Some(instruction)) = self.predecoded.get(start) {
+                if instruction.covers(start, address) {
+                    self.stale.insert(start);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_resolve_operands() {
+        let memory = Memory::new(vec![21001, -1, 7, 3]);
+        let instruction = DecodedInstruction::decode(&memory, 0).expect("Failed to decode");
+        assert_eq!(
+            Operation::Add(
+                ParameterMode::Position,
+                ParameterMode::Immediate,
+                ParameterMode::Relative
+            ),
+            instruction.operation
+        );
+        assert_eq!(
+            [
+                Operand::Invalid(-1),
+                Operand::Immediate(7),
+                Operand::Relative(3)
+            ],
+            instruction.operands
+        );
+        assert_eq!(4, instruction.length());
+
+        let instruction = DecodedInstruction::decode(&memory, 3).expect("Failed to decode");
+        assert_eq!(
+            Operation::StoreInput(ParameterMode::Position),
+            instruction.operation
+        );
+        assert_eq!(Operand::Address(0), instruction.operands[0]);
+        assert_eq!(Err(-1), DecodedInstruction::decode(&memory, 1));
+    }
+
+    #[test]
+    fn it_should_invalidate_instructions_covering_a_write() {
+        let memory = Memory::new(vec![1, 0, 0, 0, 99, 104, 1]);
+        let mut cache = DecodeCache::new(memory.len());
+        for address in &[0, 4, 5] {
+            let instruction =
+                DecodedInstruction::decode(&memory, *address).expect("Failed to decode");
+            cache.insert(*address, instruction);
+        }
+
+        cache.invalidate(3);
+        assert_eq!(None, cache.get(0));
+        assert!(cache.get(4).is_some());
+        assert!(cache.get(5).is_some());
+
+        cache.invalidate(6);
+        assert!(cache.get(4).is_some());
+        assert_eq!(None, cache.get(5));
+
+        cache.invalidate(100);
+        assert!(cache.get(4).is_some());
+    }
+
+    #[test]
+    fn it_should_only_cache_addresses_that_it_covers() {
+        let mut memory = Memory::new(vec![104, 1, 99]);
+        memory.set(1_000_000, 99);
+        let mut cache = DecodeCache::new(3);
+        for &address in &[0, 2, 1_000_000] {
+            let instruction =
+                DecodedInstruction::decode(&memory, address).expect("Failed to decode");
+            cache.insert(address, instruction);
+        }
+        assert!(cache.get(0).is_some());
+        assert!(cache.get(2).is_some());
+        assert_eq!(None, cache.get(1_000_000));
+        assert_eq!(3, cache.instructions.len());
+
+        let instruction = DecodedInstruction::decode(&memory, 2).expect("Failed to decode");
+        cache.insert(usize::MAX, instruction);
+        assert_eq!(None, cache.get(usize::MAX));
+    }
+
+    #[test]
+    fn it_should_share_predecoded_instructions_between_clones() {
+        let memory = Memory::new(vec![1, 0, 0, 0, 99, 104, 1]);
+        let cache = DecodeCache::predecode(&memory, 7);
+        assert_eq!(
+            Some(Operation::Terminate),
+            cache.get(4).map(|i| i.operation)
+        );
+        // zero is not an operation code
+        assert_eq!(None, cache.get(1));
+
+        let mut clone = cache.clone();
+        clone.invalidate(2);
+        assert_eq!(None, clone.get(0));
+        assert!(clone.get(4).is_some());
+        assert!(cache.get(0).is_some());
+
+        // decoding the instruction again replaces the stale one
+        let patched = Memory::new(vec![2, 0, 0, 0]);
+        let instruction = DecodedInstruction::decode(&patched, 0).expect("Failed to decode");
+        clone.insert(0, instruction);
+        assert_eq!(
+            Some(Operation::Multiply(
+                ParameterMode::Position,
+                ParameterMode::Position,
+                ParameterMode::Position
+            )),
+            clone.get(0).map(|i| i.operation)
+        );
+        assert_eq!(
+            Some(Operation::Add(
+                ParameterMode::Position,
+                ParameterMode::Position,
+                ParameterMode::Position
+            )),
+            cache.get(0).map(|i| i.operation)
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+use std::sync::mpsc::{Receiver, Sender, SyncSender};
+
+/// Somewhere that an `IntcodeComputer` can read its input from
+pub trait InputSource<W> {
+    /// Provides the next input value, or `None` if there is no input available at the moment
+    fn next_input(&mut self) -> io::Result<Option<W>>;
+}
+
+/// Somewhere that an `IntcodeComputer` can send its output to
+pub trait OutputSink<W> {
+    fn send_output(&mut self, value: W) -> io::Result<()>;
+}
+
+impl<W> InputSource<W> for VecDeque<W> {
+    fn next_input(&mut self) -> io::Result<Option<W>> {
+        Ok(self.pop_front())
+    }
+}
+
+impl<W> OutputSink<W> for VecDeque<W> {
+    fn send_output(&mut self, value: W) -> io::Result<()> {
+        self.push_back(value);
+        Ok(())
+    }
+}
+
+impl<W> OutputSink<W> for Vec<W> {
+    fn send_output(&mut self, value: W) -> io::Result<()> {
+        self.push(value);
+        Ok(())
+    }
+}
+
+/// Blocks until a value is received, treating a disconnected channel as having no more input
+impl<W> InputSource<W> for Receiver<W> {
+    fn next_input(&mut self) -> io::Result<Option<W>> {
+        Ok(self.recv().ok())
+    }
+}
+
+impl<W> OutputSink<W> for Sender<W> {
+    fn send_output(&mut self, value: W) -> io::Result<()> {
+        self.send(value).map_err(|_| disconnected())
+    }
+}
+
+impl<W> OutputSink<W> for SyncSender<W> {
+    fn send_output(&mut self, value: W) -> io::Result<()> {
+        self.send(value).map_err(|_| disconnected())
+    }
+}
+
+fn disconnected() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "Output channel is disconnected")
+}
+
+/// Input drawn from an iterator
+pub struct IterInput<I>(pub I);
+
+impl<W, I: Iterator<Item = W>> InputSource<W> for IterInput<I> {
+    fn next_input(&mut self) -> io::Result<Option<W>> {
+        Ok(self.0.next())
+    }
+}
+
+/// Input computed by a closure each time the program asks for it
+pub struct FnInput<F>(pub F);
+
+impl<W, F: FnMut() -> Option<W>> InputSource<W> for FnInput<F> {
+    fn next_input(&mut self) -> io::Result<Option<W>> {
+        Ok((self.0)())
+    }
+}
+
+/// Output handed to a closure as soon as the program produces it
+pub struct FnOutput<F>(pub F);
+
+impl<W, F: FnMut(W)> OutputSink<W> for FnOutput<F> {
+    fn send_output(&mut self, value: W) -> io::Result<()> {
+        (self.0)(value);
+        Ok(())
+    }
+}
+
+/// Input parsed from a reader, where values are separated by commas or whitespace
+pub struct ReaderInput<R> {
+    reader: R,
+    pending: VecDeque<String>,
+}
+
+impl<R: BufRead> ReaderInput<R> {
+    pub fn new(reader: R) -> Self {
+        ReaderInput {
+            reader,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<W: FromStr, R: BufRead> InputSource<W> for ReaderInput<R> {
+    fn next_input(&mut self) -> io::Result<Option<W>> {
+        while self.pending.is_empty() {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            self.pending.extend(
+                line.split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|token| !token.is_empty())
+                    .map(String::from),
+            );
+        }
+        let token = self.pending.pop_front().unwrap_or_default();
+        token.parse().map(Some).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unable to parse input value {:?}", token),
+            )
+        })
+    }
+}
+
+/// Output written to a writer, one value per line
+pub struct WriterOutput<T>(pub T);
+
+impl<W: std::fmt::Display, T: Write> OutputSink<W> for WriterOutput<T> {
+    fn send_output(&mut self, value: W) -> io::Result<()> {
+        writeln!(self.0, "{}", value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn it_should_read_input_from_a_queue() {
+        let mut input = VecDeque::from(vec![1, 2]);
+        assert_eq!(Some(1), input.next_input().unwrap());
+        assert_eq!(Some(2), input.next_input().unwrap());
+        assert_eq!(None::<i32>, input.next_input().unwrap());
+    }
+
+    #[test]
+    fn it_should_read_input_from_an_iterator_and_a_closure() {
+        let mut input = IterInput(1..3);
+        assert_eq!(Some(1), input.next_input().unwrap());
+        assert_eq!(Some(2), input.next_input().unwrap());
+        assert_eq!(None, input.next_input().unwrap());
+
+        let mut counter = 0;
+        let mut input = FnInput(|| {
+            counter += 5;
+            Some(counter)
+        });
+        assert_eq!(Some(5), input.next_input().unwrap());
+        assert_eq!(Some(10), input.next_input().unwrap());
+    }
+
+    #[test]
+    fn it_should_pass_values_over_channels() {
+        let (mut sender, mut receiver) = channel();
+        sender.send_output(4).unwrap();
+        assert_eq!(Some(4), receiver.next_input().unwrap());
+
+        drop(sender);
+        assert_eq!(None::<i32>, receiver.next_input().unwrap());
+
+        let (mut sender, receiver) = channel();
+        drop(receiver);
+        assert_eq!(
+            io::ErrorKind::BrokenPipe,
+            sender.send_output(4).unwrap_err().kind()
+        );
+    }
+
+    #[test]
+    fn it_should_parse_input_from_a_reader() {
+        let mut input = ReaderInput::new("1,2\n 3  -4\n\n".as_bytes());
+        let values: Vec<Option<i64>> = (0..5).map(|_| input.next_input().unwrap()).collect();
+        assert_eq!(vec![Some(1), Some(2), Some(3), Some(-4), None], values);
+
+        let mut input = ReaderInput::new("1,x".as_bytes());
+        assert_eq!(Some(1), input.next_input().unwrap());
+        let failure: io::Result<Option<i64>> = input.next_input();
+        assert_eq!(io::ErrorKind::InvalidData, failure.unwrap_err().kind());
+    }
+
+    #[test]
+    fn it_should_write_output_to_a_writer() {
+        let mut output = WriterOutput(Vec::new());
+        output.send_output(1).unwrap();
+        output.send_output(-20).unwrap();
+        assert_eq!("1\n-20\n", String::from_utf8(output.0).unwrap());
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::VecDeque;
+
+use super::{MemoryWrite, Word};
+
+/// Everything that an executed instruction changed, so that it can be undone
+#[derive(Debug, PartialEq, Clone)]
+pub struct JournalEntry<W: Word = i64> {
+    /// The number of instructions that were executed before this one
+    pub step: u64,
+    /// The address of the instruction, which is also the program counter before it executed
+    pub address: usize,
+    pub memory_write: Option<MemoryWrite<W>>,
+    /// The relative base before the instruction adjusted it
+    pub previous_relative_base: Option<W>,
+    pub input: Option<W>,
+    pub output: Option<W>,
+    pub halted: bool,
+    /// Whether the output has left the output queue, after which it can no longer be retracted
+    pub(super) output_delivered: bool,
+}
+
+/// A bounded history of executed instructions, where the oldest entries are discarded once the
+/// capacity is reached
+#[derive(Debug, PartialEq, Clone)]
+pub struct Journal<W: Word = i64> {
+    entries: VecDeque<JournalEntry<W>>,
+    capacity: usize,
+}
+
+impl<W: Word> Journal<W> {
+    pub fn new(capacity: usize) -> Self {
+        Journal {
+            entries: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub fn record(&mut self, entry: JournalEntry<W>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    pub fn pop(&mut self) -> Option<JournalEntry<W>> {
+        self.entries.pop_back()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// The earliest step that the history can be rewound to
+    pub fn earliest_step(&self) -> Option<u64> {
+        self.entries.front().map(|entry| entry.step)
+    }
+
+    /// Marks the most recent outputs that have not been delivered yet as delivered
+    pub fn mark_outputs_delivered(&mut self, count: usize) {
+        self.entries
+            .iter_mut()
+            .rev()
+            .filter(|entry| entry.output.is_some() && !entry.output_delivered)
+            .take(count)
+            .for_each(|entry| entry.output_delivered = true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(step: u64, output: Option<i64>) -> JournalEntry {
+        JournalEntry {
+            step,
+            address: 0,
+            memory_write: None,
+            previous_relative_base: None,
+            input: None,
+            output,
+            halted: false,
+            output_delivered: false,
+        }
+    }
+
+    #[test]
+    fn it_should_discard_the_oldest_entries() {
+        let mut journal = Journal::new(2);
+        (0..5).for_each(|step| journal.record(entry(step, None)));
+        assert_eq!(Some(3), journal.earliest_step());
+        assert_eq!(Some(4), journal.pop().map(|entry| entry.step));
+        assert_eq!(Some(3), journal.pop().map(|entry| entry.step));
+        assert_eq!(None, journal.pop());
+        assert_eq!(None, journal.earliest_step());
+    }
+
+    #[test]
+    fn it_should_mark_queued_outputs_as_delivered() {
+        let mut journal = Journal::new(10);
+        journal.record(entry(0, Some(1)));
+        journal.record(entry(1, None));
+        journal.record(entry(2, Some(2)));
+        journal.record(entry(3, Some(3)));
+        journal.mark_outputs_delivered(1);
+        let delivered = |journal: &Journal| -> Vec<bool> {
+            journal
+                .entries
+                .iter()
+                .map(|entry| entry.output_delivered)
+                .collect()
+        };
+        assert_eq!(vec![false, false, false, true], delivered(&journal));
+        journal.mark_outputs_delivered(2);
+        assert_eq!(vec![true, false, true, true], delivered(&journal));
+    }
+}
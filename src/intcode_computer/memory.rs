@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use super::Word;
+
+const PAGE_SIZE: usize = 1024;
+
+/// Memory for an Intcode program. The loaded program image is kept as a contiguous vector,
+/// while any address past its end is stored in lazily allocated pages, so that unset cells read
+/// as zero and distant writes only allocate the page that they land in.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Memory<W> {
+    image: Vec<W>,
+    pages: HashMap<usize, Vec<W>>,
+    length: usize,
+    zero: W,
+}
+
+impl<W: Word> Memory<W> {
+    pub fn new(image: Vec<W>) -> Self {
+        let length = image.len();
+        Self {
+            image,
+            pages: HashMap::new(),
+            length,
+            zero: W::zero(),
+        }
+    }
+
+    pub fn get(&self, address: usize) -> &W {
+        if let Some(value) = self.image.get(address) {
+            return value;
+        }
+        self.pages
+            .get(&(address / PAGE_SIZE))
+            .map(|page| &page[address % PAGE_SIZE])
+            .unwrap_or(&self.zero)
+    }
+
+    pub fn set(&mut self, address: usize, value: W) {
+        if let Some(cell) = self.image.get_mut(address) {
+            *cell = value;
+            return;
+        }
+        let page = self
+            .pages
+            .entry(address / PAGE_SIZE)
+            .or_insert_with(|| vec![W::zero(); PAGE_SIZE]);
+        page[address % PAGE_SIZE] = value;
+        self.length = self.length.max(address.saturating_add(1));
+    }
+
+    /// Rebuilds memory from an image, the cells outside of it and the length it had
+    pub fn from_parts(
+        image: Vec<W>,
+        cells: impl IntoIterator<Item = (usize, W)>,
+        length: usize,
+    ) -> Self {
+        let mut memory = Self::new(image);
+        for (address, value) in cells {
+            memory.set(address, value);
+        }
+        memory.length = memory.length.max(length);
+        memory
+    }
+
+    /// The number of addressable cells up to and including the highest cell that has been set
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn image(&self) -> &[W] {
+        &self.image
+    }
+
+    /// The cells past the end of the image that hold something other than zero, in order
+    pub fn cells_outside_image(&self) -> Vec<(usize, W)> {
+        let mut pages: Vec<_> = self.pages.iter().collect();
+        pages.sort_by_key(|(page, _)| **page);
+        pages
+            .into_iter()
+            .flat_map(|(page, cells)| {
+                cells
+                    .iter()
+                    .enumerate()
+                    .map(move |(offset, value)| (page * PAGE_SIZE + offset, value))
+            })
+            .filter(|(address, value)| *address >= self.image.len() && !value.is_zero())
+            .map(|(address, value)| (address, value.clone()))
+            .collect()
+    }
+
+    #[cfg(test)]
+    pub fn to_vec(&self) -> Vec<W> {
+        (0..self.length)
+            .map(|address| self.get(address).clone())
+            .collect()
+    }
+}
+
+/// Hashes the contents of memory, so that equal contents hash the same no matter which pages
+/// happen to have been allocated
+impl<W: Word> Hash for Memory<W> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.image.hash(state);
+        let mut pages: Vec<_> = self
+            .pages
+            .iter()
+            .filter(|(_, page)| page.iter().any(|cell| !cell.is_zero()))
+            .collect();
+        pages.sort_by_key(|(page, _)| **page);
+        pages.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_read_the_loaded_image() {
+        let memory = Memory::new(vec![1, 2, 3]);
+        assert_eq!(&1, memory.get(0));
+        assert_eq!(&3, memory.get(2));
+        assert_eq!(3, memory.len());
+        assert_eq!(vec![1, 2, 3], memory.to_vec());
+    }
+
+    #[test]
+    fn it_should_read_unset_cells_as_zero() {
+        let memory = Memory::new(vec![1, 2, 3]);
+        assert_eq!(&0, memory.get(3));
+        assert_eq!(&0, memory.get(1_000_000));
+        assert_eq!(3, memory.len());
+    }
+
+    #[test]
+    fn it_should_write_inside_and_outside_of_the_image() {
+        let mut memory = Memory::new(vec![1, 2, 3]);
+        memory.set(1, 5);
+        memory.set(4, 7);
+        assert_eq!(vec![1, 5, 3, 0, 7], memory.to_vec());
+        assert!(memory.pages.contains_key(&0));
+    }
+
+    #[test]
+    fn it_should_rebuild_memory_from_its_parts() {
+        let mut memory = Memory::new(vec![1, 2, 3]);
+        memory.set(5, 4);
+        memory.set(1_000_000, 42);
+        memory.set(2_000_000, 0);
+        assert_eq!(vec![(5, 4), (1_000_000, 42)], memory.cells_outside_image());
+
+        let rebuilt = Memory::from_parts(
+            memory.image().to_vec(),
+            memory.cells_outside_image(),
+            memory.len(),
+        );
+        assert_eq!(2_000_001, rebuilt.len());
+        assert_eq!(&42, rebuilt.get(1_000_000));
+        assert_eq!(&4, rebuilt.get(5));
+    }
+
+    #[test]
+    fn it_should_only_allocate_the_page_of_a_distant_write() {
+        let mut memory = Memory::new(vec![1, 2, 3]);
+        memory.set(1_000_000, 42);
+        assert_eq!(&42, memory.get(1_000_000));
+        assert_eq!(&0, memory.get(999_999));
+        assert_eq!(1_000_001, memory.len());
+        assert_eq!(1, memory.pages.len());
+        assert_eq!(3, memory.image.len());
+    }
+
+    #[test]
+    fn it_should_write_to_the_highest_address() {
+        let mut memory = Memory::new(vec![1, 2, 3]);
+        memory.set(usize::MAX, 42);
+        assert_eq!(&42, memory.get(usize::MAX));
+        assert_eq!(usize::MAX, memory.len());
+        assert_eq!(vec![(usize::MAX, 42)], memory.cells_outside_image());
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::{ExecutionEvent, Operation, ParameterMode, Word};
+use crate::disassembler::{mnemonic, Instruction};
+
+/// How often a conditional jump at one address was taken
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub struct BranchCounts {
+    pub taken: u64,
+    pub not_taken: u64,
+}
+
+impl BranchCounts {
+    pub fn total(&self) -> u64 {
+        self.taken + self.not_taken
+    }
+
+    pub fn taken_ratio(&self) -> f64 {
+        ratio(self.taken, self.total())
+    }
+}
+
+/// Counts of the instructions that an `IntcodeComputer` has executed while profiling
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Profile {
+    instructions: u64,
+    opcodes: BTreeMap<i32, u64>,
+    operations: BTreeMap<i32, u64>,
+    addresses: BTreeMap<usize, u64>,
+    branches: BTreeMap<usize, BranchCounts>,
+}
+
+impl Profile {
+    pub fn record<W: Word>(&mut self, event: &ExecutionEvent<W>) {
+        let code = event.operation.encode();
+        self.instructions += 1;
+        *self.opcodes.entry(code % 100).or_default() += 1;
+        *self.operations.entry(code).or_default() += 1;
+        *self.addresses.entry(event.address).or_default() += 1;
+        if let Operation::JumpIfTrue(_, _) | Operation::JumpIfFalse(_, _) = event.operation {
+            let branch = self.branches.entry(event.address).or_default();
+            // the target is only fetched when the jump is taken
+            if event.operands.len() == 2 {
+                branch.taken += 1;
+            } else {
+                branch.not_taken += 1;
+            }
+        }
+    }
+
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    /// Executed instructions by opcode, ignoring their parameter modes
+    pub fn opcodes(&self) -> &BTreeMap<i32, u64> {
+        &self.opcodes
+    }
+
+    /// Executed instructions by their encoded operation, which distinguishes every combination of
+    /// parameter modes
+    pub fn operations(&self) -> &BTreeMap<i32, u64> {
+        &self.operations
+    }
+
+    pub fn addresses(&self) -> &BTreeMap<usize, u64> {
+        &self.addresses
+    }
+
+    /// The conditional jumps that have been executed, by address
+    pub fn branches(&self) -> &BTreeMap<usize, BranchCounts> {
+        &self.branches
+    }
+
+    /// The `count` most executed addresses, from the hottest down
+    pub fn hottest_addresses(&self, count: usize) -> Vec<(usize, u64)> {
+        let mut addresses: Vec<(usize, u64)> = self
+            .addresses
+            .iter()
+            .map(|(&address, &executed)| (address, executed))
+            .collect();
+        addresses.sort_by_key(|&(address, executed)| (std::cmp::Reverse(executed), address));
+        addresses.truncate(count);
+        addresses
+    }
+}
+
+/// A readable summary of a profile, listing the hottest addresses alongside the instructions that
+/// are currently in memory there, or the word that is there if it is no longer an instruction
+pub struct ProfileReport<'a, W: Word = i64> {
+    pub profile: &'a Profile,
+    pub hot_spots: Vec<(usize, u64, Result<Instruction<W>, W>)>,
+}
+
+impl<'a, W: Word> fmt::Display for ProfileReport<'a, W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let total = self.profile.instructions;
+        writeln!(f, "Executed {} instructions", total)?;
+
+        writeln!(f, "\nOpcodes:")?;
+        for (&opcode, &executed) in &self.profile.opcodes {
+            let name = describe(opcode, |operation| mnemonic(&operation).to_string());
+            writeln!(
+                f,
+                "  {:<24} {:>12} {:>6.1}%",
+                name,
+                executed,
+                percent(executed, total)
+            )?;
+        }
+
+        writeln!(f, "\nParameter modes:")?;
+        let mut operations: Vec<_> = self.profile.operations.iter().collect();
+        operations.sort_by_key(|(&code, _)| (code % 100, code));
+        for (&code, &executed) in operations {
+            let name = describe(code, describe_modes);
+            writeln!(
+                f,
+                "  {:<24} {:>12} {:>6.1}%",
+                name,
+                executed,
+                percent(executed, total)
+            )?;
+        }
+
+        if !self.profile.branches.is_empty() {
+            writeln!(f, "\nBranches:")?;
+            for (address, branch) in &self.profile.branches {
+                writeln!(
+                    f,
+                    "  {:>6}: taken {} of {} ({:.1}%)",
+                    address,
+                    branch.taken,
+                    branch.total(),
+                    100.0 * branch.taken_ratio()
+                )?;
+            }
+        }
+
+        writeln!(f, "\nHottest addresses:")?;
+        for (address, executed, instruction) in &self.hot_spots {
+            let instruction = match instruction {
+                Ok(instruction) => instruction.to_string(),
+                Err(word) => format!("DATA {}", word),
+            };
+            writeln!(
+                f,
+                "  {:>6}: {:>12} {:>6.1}%  {}",
+                address,
+                executed,
+                percent(*executed, total),
+                instruction
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn describe(code: i32, name: impl Fn(Operation) -> String) -> String {
+    Operation::decode(&i64::from(code)).map_or_else(|_| code.to_string(), name)
+}
+
+/// Names an operation along with the modes of its parameters, such as `MUL  [a], #b, [rb+c]`
+fn describe_modes(operation: Operation) -> String {
+    let parameters: Vec<String> = operation
+        .parameter_modes()
+        .into_iter()
+        .zip(["a", "b", "c"].iter())
+        .map(|(mode, name)| match mode {
+            ParameterMode::Position => format!("[{}]", name),
+            ParameterMode::Immediate => format!("#{}", name),
+            ParameterMode::Relative => format!("[rb+{}]", name),
+        })
+        .collect();
+    format!("{:<4} {}", mnemonic(&operation), parameters.join(", "))
+        .trim_end()
+        .to_string()
+}
+
+fn ratio(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    100.0 * ratio(part, total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::intcode_computer::IntcodeComputer;
+
+    // counts address 12 down from three to zero
+    const COUNTDOWN: [i64; 13] = [1001, 12, -1, 12, 1005, 12, 0, 4, 12, 99, 0, 0, 3];
+
+    fn profile_countdown() -> IntcodeComputer {
+        let mut computer = IntcodeComputer::new(COUNTDOWN.to_vec());
+        computer.enable_profiling();
+        computer.execute().expect("Failed to execute program");
+        computer
+    }
+
+    #[test]
+    fn it_should_count_instructions() {
+        let computer = profile_countdown();
+        let profile = computer.profile().expect("Missing profile");
+        assert_eq!(8, profile.instructions());
+        assert_eq!(
+            vec![(1, 3), (4, 1), (5, 3), (99, 1)],
+            profile
+                .opcodes()
+                .iter()
+                .map(|(&opcode, &executed)| (opcode, executed))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some(&3), profile.operations().get(&1001));
+        assert_eq!(Some(&3), profile.operations().get(&1005));
+        assert_eq!(
+            Some(&BranchCounts {
+                taken: 2,
+                not_taken: 1
+            }),
+            profile.branches().get(&4)
+        );
+        assert_eq!(vec![(0, 3), (4, 3)], profile.hottest_addresses(2));
+    }
+
+    #[test]
+    fn it_should_report_the_hottest_addresses() {
+        let computer = profile_countdown();
+        let report = computer
+            .profile_report(1)
+            .expect("Missing profile")
+            .to_string();
+        assert!(report.starts_with("Executed 8 instructions\n"));
+        assert!(report.contains("\n  ADD  [a], #b, [c]                   3   37.5%\n"));
+        assert!(report.contains("\n       4: taken 2 of 3 (66.7%)\n"));
+        assert!(report.ends_with(
+            "Hottest addresses:\n       0:            3   37.5%  ADD  [12], #-1, [12]\n"
+        ));
+    }
+}
@@ -0,0 +1,140 @@
+use super::{MemoryWrite, Word};
+
+/// What to do when a program writes over an address that it has already executed, either as an
+/// operation code or as one of its parameters
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub enum SelfModificationPolicy {
+    /// Let the write happen without keeping track of anything
+    #[default]
+    Allow,
+    /// Let the write happen, but record it in the self-modification report
+    Warn,
+    /// Fail with `ExecutionError::SelfModifyingCode` instead of making the write
+    Error,
+}
+
+/// A write that landed on an address that had already been executed
+#[derive(Debug, PartialEq, Clone)]
+pub struct SelfModification<W: Word = i64> {
+    /// The number of instructions that were executed before the one that made the write
+    pub step: u64,
+    /// The address of the instruction that made the write
+    pub index: usize,
+    pub write: MemoryWrite<W>,
+}
+
+/// Remembers every address that has been executed
+#[derive(Debug, Clone)]
+pub struct CodeTracker<W: Word> {
+    pub policy: SelfModificationPolicy,
+    executed: Vec<bool>,
+    pub report: Vec<SelfModification<W>>,
+}
+
+impl<W: Word> CodeTracker<W> {
+    pub fn new(policy: SelfModificationPolicy) -> Self {
+        CodeTracker {
+            policy,
+            executed: Vec::new(),
+            report: Vec::new(),
+        }
+    }
+
+    /// Marks an instruction's operation code and its parameters as executed
+    pub fn mark_executed(&mut self, address: usize, parameters: usize) {
+        let end = address + 1 + parameters;
+        if self.executed.len() < end {
+            self.executed.resize(end, false);
+        }
+        for cell in &mut self.executed[address..end] {
+            *cell = true;
+        }
+    }
+
+    pub fn is_executed(&self, address: usize) -> bool {
+        self.executed.get(address).copied().unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::intcode_computer::{ExecutionError, ExecutionState, IntcodeComputer};
+
+    // adds one to the parameter of its own output instruction, then outputs it
+    const PATCHING: [i64; 8] = [1001, 5, 1, 5, 104, 41, 99, 0];
+
+    #[test]
+    fn it_should_track_executed_addresses() {
+        let mut tracker: CodeTracker<i64> = CodeTracker::new(SelfModificationPolicy::Warn);
+        tracker.mark_executed(2, 3);
+        assert!(!tracker.is_executed(1));
+        assert!((2..6).all(|address| tracker.is_executed(address)));
+        assert!(!tracker.is_executed(6));
+        assert!(!tracker.is_executed(1000));
+    }
+
+    #[test]
+    fn it_should_allow_self_modification_by_default() {
+        let mut computer = IntcodeComputer::new(PATCHING.to_vec());
+        assert_eq!(Ok(ExecutionState::Halted), computer.execute());
+        assert_eq!(Some(42), computer.take_output().pop_front());
+        assert_eq!(&[] as &[SelfModification], computer.self_modifications());
+    }
+
+    #[test]
+    fn it_should_only_flag_writes_over_executed_code() {
+        let mut computer = IntcodeComputer::new(PATCHING.to_vec());
+        computer.set_self_modification_policy(SelfModificationPolicy::Warn);
+        assert_eq!(Ok(ExecutionState::Halted), computer.execute());
+        assert_eq!(Some(42), computer.take_output().pop_front());
+        // the output instruction had not been executed yet when it was patched
+        assert_eq!(&[] as &[SelfModification], computer.self_modifications());
+
+        // writes over its own operation code on every pass of the loop
+        let mut computer = IntcodeComputer::new(vec![1001, 0, 0, 0, 1105, 1, 0]);
+        computer.set_self_modification_policy(SelfModificationPolicy::Warn);
+        computer.set_step_limit(Some(4));
+        assert!(computer.execute().is_err());
+        assert_eq!(
+            vec![
+                SelfModification {
+                    step: 0,
+                    index: 0,
+                    write: MemoryWrite {
+                        address: 0,
+                        previous: 1001,
+                        value: 1001
+                    }
+                },
+                SelfModification {
+                    step: 2,
+                    index: 0,
+                    write: MemoryWrite {
+                        address: 0,
+                        previous: 1001,
+                        value: 1001
+                    }
+                }
+            ],
+            computer.self_modifications()
+        );
+    }
+
+    #[test]
+    fn it_should_refuse_to_modify_executed_code() {
+        // patches the parameter of the output instruction after it has run once
+        let mut computer = IntcodeComputer::new(vec![104, 7, 1001, 1, 1, 1, 1105, 1, 0]);
+        computer.set_self_modification_policy(SelfModificationPolicy::Error);
+        assert_eq!(
+            Err(ExecutionError::SelfModifyingCode {
+                index: 2,
+                address: 1
+            }),
+            computer.execute()
+        );
+        assert_eq!(&7, computer.read_memory(1));
+        assert_eq!(2, computer.program_counter());
+    }
+}
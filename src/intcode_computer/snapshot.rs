@@ -0,0 +1,245 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::Word;
+
+/// The version of the snapshot format that this build reads and writes
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// The bytes that every binary snapshot starts with
+const MAGIC: &[u8; 4] = b"ICSN";
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("Unable to access the snapshot: {0}")]
+    Io(#[from] io::Error),
+    #[error("Invalid JSON snapshot: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid binary snapshot: {0}")]
+    Binary(#[from] bincode::Error),
+    #[error("Snapshot has version {found}, but only version {supported} is supported")]
+    UnsupportedVersion { found: u32, supported: u32 },
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum SnapshotFormat {
+    /// Pretty printed JSON
+    Json,
+    /// The magic bytes `ICSN`, the version as a little endian `u32`, then the bincode encoded
+    /// snapshot
+    Binary,
+}
+
+/// The complete state of an `IntcodeComputer`, from which it can continue exactly where it left
+/// off. The tracer and the journal are not part of the machine state, so they are not included.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Snapshot<W: Word = i64> {
+    pub version: u32,
+    pub program_counter: usize,
+    pub relative_base: W,
+    /// The memory that the program was loaded into, including any changes made to it
+    pub image: Vec<W>,
+    /// The non-zero cells past the end of the image, in order of their addresses
+    pub cells: Vec<(usize, W)>,
+    pub memory_length: usize,
+    pub input: VecDeque<W>,
+    pub output: VecDeque<W>,
+    pub halted: bool,
+    pub steps: u64,
+}
+
+#[derive(Deserialize)]
+struct Version {
+    version: u32,
+}
+
+impl<W: Word + Serialize + DeserializeOwned> Snapshot<W> {
+    pub fn to_json(&self) -> Result<String, SnapshotError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, SnapshotError> {
+        let Version { version } = serde_json::from_str(json)?;
+        check_version(version)?;
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SnapshotError> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bincode::serialize_into(&mut bytes, self)?;
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let payload = bytes
+            .strip_prefix(&MAGIC[..])
+            .filter(|payload| payload.len() >= 4)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Missing the binary snapshot header",
+                )
+            })?;
+        let (version, payload) = payload.split_at(4);
+        check_version(u32::from_le_bytes([
+            version[0], version[1], version[2], version[3],
+        ]))?;
+        Ok(bincode::deserialize(payload)?)
+    }
+
+    pub fn save(
+        &self,
+        path: impl AsRef<Path>,
+        format: SnapshotFormat,
+    ) -> Result<(), SnapshotError> {
+        let bytes = match format {
+            SnapshotFormat::Json => self.to_json()?.into_bytes(),
+            SnapshotFormat::Binary => self.to_bytes()?,
+        };
+        Ok(fs::write(path, bytes)?)
+    }
+
+    /// Loads a snapshot in either format, telling them apart by the binary header
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        let bytes = fs::read(path)?;
+        if bytes.starts_with(MAGIC) {
+            Self::from_bytes(&bytes)
+        } else {
+            let json = String::from_utf8(bytes)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+            Self::from_json(&json)
+        }
+    }
+}
+
+fn check_version(version: u32) -> Result<(), SnapshotError> {
+    if version == SNAPSHOT_VERSION {
+        Ok(())
+    } else {
+        Err(SnapshotError::UnsupportedVersion {
+            found: version,
+            supported: SNAPSHOT_VERSION,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::intcode_computer::{ExecutionState, IntcodeComputer};
+
+    use num::BigInt;
+
+    // echoes its input back until it reads a zero, keeping a running total at address 1000
+    fn setup_computer() -> IntcodeComputer {
+        let mut computer =
+            IntcodeComputer::new(vec![3, 20, 1, 20, 1000, 1000, 4, 1000, 1005, 20, 0, 99]);
+        computer.push_input(5);
+        computer.push_input(6);
+        computer
+    }
+
+    #[test]
+    fn it_should_continue_from_a_snapshot() {
+        let mut computer = setup_computer();
+        assert_eq!(Ok(ExecutionState::AwaitingInput), computer.execute());
+        let mut restored = IntcodeComputer::from_snapshot(computer.snapshot());
+        assert_eq!(computer.snapshot(), restored.snapshot());
+
+        for computer in [&mut computer, &mut restored].iter_mut() {
+            computer.push_input(0);
+            assert_eq!(Ok(ExecutionState::Halted), computer.execute());
+        }
+        assert_eq!(computer.snapshot(), restored.snapshot());
+        assert_eq!(VecDeque::from(vec![5, 11, 11]), restored.take_output());
+    }
+
+    #[test]
+    fn it_should_round_trip_through_both_formats() {
+        let mut computer = setup_computer();
+        computer.execute().expect("Failed to execute program");
+        let snapshot = computer.snapshot();
+        assert_eq!(vec![(20, 6), (1000, 11)], snapshot.cells);
+        assert_eq!(1001, snapshot.memory_length);
+
+        let json = snapshot.to_json().expect("Failed to write JSON");
+        assert_eq!(
+            snapshot,
+            Snapshot::from_json(&json).expect("Failed to read JSON")
+        );
+        let bytes = snapshot.to_bytes().expect("Failed to write bytes");
+        assert_eq!(&b"ICSN\x01\0\0\0"[..], &bytes[..8]);
+        assert_eq!(
+            snapshot,
+            Snapshot::from_bytes(&bytes).expect("Failed to read bytes")
+        );
+        assert!(bytes.len() < json.len());
+    }
+
+    #[test]
+    fn it_should_save_and_load_files() {
+        let snapshot = setup_computer().snapshot();
+        let directory = std::env::temp_dir();
+        for (name, format) in [
+            ("snapshot-test.json", SnapshotFormat::Json),
+            ("snapshot-test.bin", SnapshotFormat::Binary),
+        ]
+        .iter()
+        {
+            let path = directory.join(format!("{}-{}", std::process::id(), name));
+            snapshot.save(&path, *format).expect("Failed to save");
+            let loaded: Snapshot = Snapshot::load(&path).expect("Failed to load");
+            fs::remove_file(&path).expect("Failed to clean up");
+            assert_eq!(snapshot, loaded);
+        }
+    }
+
+    #[test]
+    fn it_should_reject_other_versions() {
+        let mut snapshot = setup_computer().snapshot();
+        snapshot.version = 2;
+        let json = snapshot.to_json().expect("Failed to write JSON");
+        match Snapshot::<i64>::from_json(&json) {
+            Err(SnapshotError::UnsupportedVersion {
+                found: 2,
+                supported: 1,
+            }) => {}
+            result => panic!("Unexpected result {:?}", result),
+        }
+        let bytes = snapshot.to_bytes().expect("Failed to write bytes");
+        assert!(matches!(
+            Snapshot::<i64>::from_bytes(&bytes),
+            Err(SnapshotError::UnsupportedVersion { found: 2, .. })
+        ));
+        assert!(matches!(
+            Snapshot::<i64>::from_bytes(b"ICS"),
+            Err(SnapshotError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn it_should_snapshot_big_integers() {
+        let mut computer: IntcodeComputer<BigInt> = IntcodeComputer::new(vec![
+            BigInt::from(104),
+            num::pow(BigInt::from(3), 100),
+            BigInt::from(99),
+        ]);
+        computer.execute().expect("Failed to execute program");
+        let json = computer.snapshot().to_json().expect("Failed to write JSON");
+        let restored = IntcodeComputer::from_snapshot(
+            Snapshot::<BigInt>::from_json(&json).expect("Failed to read JSON"),
+        );
+        assert_eq!(
+            &VecDeque::from(vec![num::pow(BigInt::from(3), 100)]),
+            restored.output_queue()
+        );
+    }
+}
@@ -0,0 +1,293 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::Path;
+
+use super::{ExecutionEvent, Operation, Word};
+
+/// A single executed instruction, as written to a trace. Words are recorded as JSON numbers when
+/// they fit in an `i64` and as strings otherwise.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct TraceRecord {
+    /// The number of instructions that were executed before this one
+    pub step: u64,
+    pub program_counter: usize,
+    pub opcode: Value,
+    pub operation: Operation,
+    pub operands: Vec<Value>,
+    pub memory_write: Option<TraceWrite>,
+    pub input: Option<Value>,
+    pub output: Option<Value>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct TraceWrite {
+    pub address: usize,
+    pub previous: Value,
+    pub value: Value,
+}
+
+impl TraceRecord {
+    pub fn new<W: Word>(step: u64, opcode: &W, event: &ExecutionEvent<W>) -> Self {
+        TraceRecord {
+            step,
+            program_counter: event.address,
+            opcode: to_json(opcode),
+            operation: event.operation,
+            operands: event.operands.iter().map(to_json).collect(),
+            memory_write: event.memory_write.as_ref().map(|write| TraceWrite {
+                address: write.address,
+                previous: to_json(&write.previous),
+                value: to_json(&write.value),
+            }),
+            input: event.input.as_ref().map(to_json),
+            output: event.output.as_ref().map(to_json),
+        }
+    }
+}
+
+fn to_json<W: Word>(word: &W) -> Value {
+    word.to_i64()
+        .map(Value::from)
+        .unwrap_or_else(|| Value::String(word.to_string()))
+}
+
+/// Writes a JSON Lines record for every instruction that an `IntcodeComputer` executes,
+/// optionally limited to a range of addresses, a set of opcodes, and a maximum size
+pub struct Tracer {
+    writer: Box<dyn Write + Send>,
+    addresses: Option<Range<usize>>,
+    opcodes: Option<BTreeSet<i32>>,
+    max_records: Option<usize>,
+    max_bytes: Option<usize>,
+    records_written: usize,
+    bytes_written: usize,
+    full: bool,
+}
+
+impl Tracer {
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Tracer {
+            writer: Box::new(writer),
+            addresses: None,
+            opcodes: None,
+            max_records: None,
+            max_bytes: None,
+            records_written: 0,
+            bytes_written: 0,
+            full: false,
+        }
+    }
+
+    pub fn to_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+
+    /// Only records instructions whose address is inside of the range
+    pub fn with_addresses(mut self, addresses: Range<usize>) -> Self {
+        self.addresses = Some(addresses);
+        self
+    }
+
+    /// Only records instructions with one of the opcodes (the last two digits of their code)
+    pub fn with_opcodes(mut self, opcodes: impl IntoIterator<Item = i32>) -> Self {
+        self.opcodes = Some(opcodes.into_iter().collect());
+        self
+    }
+
+    /// Stops recording once this many records have been written
+    pub fn with_max_records(mut self, max_records: usize) -> Self {
+        self.max_records = Some(max_records);
+        self
+    }
+
+    /// Stops recording before a record would take the trace past this many bytes
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    pub fn records_written(&self) -> usize {
+        self.records_written
+    }
+
+    /// Whether the trace has reached its maximum size
+    pub fn is_full(&self) -> bool {
+        self.full
+            || self
+                .max_records
+                .is_some_and(|max_records| self.records_written >= max_records)
+    }
+
+    pub fn record<W: Word>(
+        &mut self,
+        step: u64,
+        opcode: &W,
+        event: &ExecutionEvent<W>,
+    ) -> io::Result<()> {
+        if self.is_full() || !self.matches(event) {
+            return Ok(());
+        }
+        let mut line = serde_json::to_string(&TraceRecord::new(step, opcode, event))?;
+        line.push('\n');
+        if let Some(max_bytes) = self.max_bytes {
+            if self.bytes_written + line.len() > max_bytes {
+                self.full = true;
+                return Ok(());
+            }
+        }
+        self.writer.write_all(line.as_bytes())?;
+        self.records_written += 1;
+        self.bytes_written += line.len();
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    fn matches<W: Word>(&self, event: &ExecutionEvent<W>) -> bool {
+        let in_range = self
+            .addresses
+            .as_ref()
+            .is_none_or(|addresses| addresses.contains(&event.address));
+        let has_opcode = self
+            .opcodes
+            .as_ref()
+            .is_none_or(|opcodes| opcodes.contains(&(event.operation.encode() % 100)));
+        in_range && has_opcode
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::intcode_computer::{IntcodeComputer, ParameterMode};
+
+    use std::sync::{Arc, Mutex};
+
+    /// A writer whose contents can still be read after it has been handed to a tracer
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuffer {
+        fn records(&self) -> Vec<TraceRecord> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).expect("Invalid record"))
+                .collect()
+        }
+    }
+
+    fn trace(tracer: impl FnOnce(Tracer) -> Tracer) -> Vec<TraceRecord> {
+        let buffer = SharedBuffer::default();
+        let mut computer = IntcodeComputer::new_with_input(
+            vec![3, 12, 1002, 12, 3, 12, 4, 12, 1105, 0, 0, 99, 0],
+            vec![7].into(),
+        );
+        computer.set_tracer(tracer(Tracer::new(buffer.clone())));
+        computer.run_to_halt().expect("Failed to execute program");
+        buffer.records()
+    }
+
+    #[test]
+    fn it_should_record_every_instruction() {
+        let records = trace(|tracer| tracer);
+        assert_eq!(5, records.len());
+        assert_eq!(
+            TraceRecord {
+                step: 1,
+                program_counter: 2,
+                opcode: Value::from(1002),
+                operation: Operation::Multiply(
+                    ParameterMode::Position,
+                    ParameterMode::Immediate,
+                    ParameterMode::Position
+                ),
+                operands: vec![Value::from(7), Value::from(3), Value::from(12)],
+                memory_write: Some(TraceWrite {
+                    address: 12,
+                    previous: Value::from(7),
+                    value: Value::from(21),
+                }),
+                input: None,
+                output: None,
+            },
+            records[1]
+        );
+        assert_eq!(Some(Value::from(7)), records[0].input);
+        assert_eq!(Some(Value::from(21)), records[2].output);
+        assert_eq!(
+            vec![0, 1, 2, 3, 4],
+            records.iter().map(|r| r.step).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn it_should_filter_records() {
+        let records = trace(|tracer| tracer.with_addresses(2..8));
+        assert_eq!(
+            vec![2, 6],
+            records
+                .iter()
+                .map(|r| r.program_counter)
+                .collect::<Vec<_>>()
+        );
+
+        let records = trace(|tracer| tracer.with_opcodes(vec![3, 5]));
+        assert_eq!(
+            vec![0, 8],
+            records
+                .iter()
+                .map(|r| r.program_counter)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn it_should_cap_the_size_of_the_trace() {
+        assert_eq!(2, trace(|tracer| tracer.with_max_records(2)).len());
+
+        let full_size: usize = trace(|tracer| tracer)
+            .iter()
+            .map(|record| serde_json::to_string(record).unwrap().len() + 1)
+            .take(3)
+            .sum();
+        assert_eq!(3, trace(|tracer| tracer.with_max_bytes(full_size)).len());
+        assert_eq!(
+            2,
+            trace(|tracer| tracer.with_max_bytes(full_size - 1)).len()
+        );
+    }
+
+    #[test]
+    fn it_should_record_large_words_as_strings() {
+        let event = ExecutionEvent {
+            address: 0,
+            operation: Operation::ProduceOutput(ParameterMode::Immediate),
+            operands: vec![i128::from(i64::MAX) + 1],
+            memory_write: None,
+            input: None,
+            output: Some(i128::from(i64::MAX) + 1),
+            program_counter: 2,
+        };
+        let record = TraceRecord::new(0, &104, &event);
+        assert_eq!(Some(Value::from("9223372036854775808")), record.output);
+    }
+}
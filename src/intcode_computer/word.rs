@@ -0,0 +1,16 @@
+use num::{FromPrimitive, Integer, Signed, ToPrimitive};
+
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+
+/// A machine word that an `IntcodeComputer` can operate on, such as `i64`, `i128` or
+/// `num::BigInt` for programs whose arithmetic would overflow a fixed width integer
+pub trait Word:
+    Integer + Signed + Clone + Debug + Display + Hash + FromPrimitive + ToPrimitive + From<i32>
+{
+}
+
+impl<T> Word for T where
+    T: Integer + Signed + Clone + Debug + Display + Hash + FromPrimitive + ToPrimitive + From<i32>
+{
+}
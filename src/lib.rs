@@ -1,2 +1,14 @@
+pub mod assembler;
+pub mod control_flow;
+pub mod debugger;
+pub mod decompiler;
+pub mod disassembler;
 pub mod intcode_computer;
+pub mod network;
 pub mod permutations;
+pub mod program;
+#[cfg(test)]
+mod test_programs;
+pub mod threaded;
+pub mod topology;
+pub mod transpiler;
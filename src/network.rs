@@ -0,0 +1,418 @@
+use thiserror::Error;
+
+use std::collections::VecDeque;
+
+use crate::intcode_computer::{ExecutionError, IntcodeComputer, StepResult, Word};
+
+/// The address that the NAT listens on
+pub const NAT_ADDRESS: usize = 255;
+
+/// The number of instructions a machine may execute before yielding to the next one
+pub const DEFAULT_TIME_SLICE: usize = 1000;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Packet<W: Word = i64> {
+    pub destination: usize,
+    pub x: W,
+    pub y: W,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum NetworkEvent<W: Word = i64> {
+    /// A machine sent a packet to another machine or to the NAT
+    Sent { source: usize, packet: Packet<W> },
+    /// A machine sent a packet to an address that nothing is listening on
+    UnknownDestination { source: usize, packet: Packet<W> },
+    /// Every machine is waiting for input and no packets are in flight
+    Idle,
+    /// The NAT woke an idle network by resending its last packet to address zero
+    NatDelivered(Packet<W>),
+}
+
+#[derive(Debug, PartialEq, Error)]
+pub enum NetworkError<W: Word = i64> {
+    #[error("Machine {address} failed: {error}")]
+    Machine {
+        address: usize,
+        error: ExecutionError<W>,
+    },
+    #[error("The network is idle and the NAT has no packet to wake it with")]
+    Stalled,
+    #[error("Packet destination {destination} can not be represented as an address")]
+    InvalidDestination { destination: W },
+}
+
+type UnknownDestinationHook<W> = Box<dyn FnMut(usize, Packet<W>) -> Option<Packet<W>>>;
+
+struct Nic<W: Word> {
+    computer: IntcodeComputer<W>,
+    incoming: VecDeque<Packet<W>>,
+    outgoing: Vec<W>,
+    active: bool,
+}
+
+/// A day 23 style network of machines running the same program. Each machine is booted with its
+/// address as its first input, receives packets as an X and a Y input, reads -1 when no packet
+/// is waiting, and sends a packet by producing its destination, X and Y as output. The machines
+/// are scheduled cooperatively, each running until it finds no packet waiting or uses up its
+/// time slice.
+pub struct Network<W: Word = i64> {
+    nics: Vec<Nic<W>>,
+    nat: Option<Packet<W>>,
+    nat_enabled: bool,
+    time_slice: usize,
+    unknown_destination: Option<UnknownDestinationHook<W>>,
+}
+
+impl<W: Word> Network<W> {
+    pub fn new(program: Vec<W>, size: usize) -> Self {
+        let nics = (0..size)
+            .map(|address| {
+                let mut computer = IntcodeComputer::new(program.clone());
+                computer.push_input(W::from_usize(address).expect("Invalid network address"));
+                Nic {
+                    computer,
+                    incoming: VecDeque::new(),
+                    outgoing: Vec::with_capacity(3),
+                    active: true,
+                }
+            })
+            .collect();
+        Network {
+            nics,
+            nat: None,
+            nat_enabled: true,
+            time_slice: DEFAULT_TIME_SLICE,
+            unknown_destination: None,
+        }
+    }
+
+    /// Treats packets sent to the NAT address like any other unknown destination
+    pub fn without_nat(mut self) -> Self {
+        self.nat_enabled = false;
+        self
+    }
+
+    pub fn with_time_slice(mut self, time_slice: usize) -> Self {
+        self.time_slice = time_slice.max(1);
+        self
+    }
+
+    /// Calls the hook with the source and the packet whenever a packet is sent to an address that
+    /// nothing is listening on. The hook can return a packet to route in its place, which is
+    /// dropped if its destination is unknown too.
+    pub fn on_unknown_destination(
+        &mut self,
+        hook: impl FnMut(usize, Packet<W>) -> Option<Packet<W>> + 'static,
+    ) {
+        self.unknown_destination = Some(Box::new(hook));
+    }
+
+    pub fn size(&self) -> usize {
+        self.nics.len()
+    }
+
+    pub fn machine(&self, address: usize) -> Option<&IntcodeComputer<W>> {
+        self.nics.get(address).map(|nic| &nic.computer)
+    }
+
+    /// The last packet that the NAT received
+    pub fn nat_packet(&self) -> Option<&Packet<W>> {
+        self.nat.as_ref()
+    }
+
+    /// Queues a packet for delivery as though a machine outside of the network had sent it
+    pub fn send(&mut self, packet: Packet<W>) -> bool {
+        match self.nics.get_mut(packet.destination) {
+            Some(nic) => {
+                nic.incoming.push_back(packet);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Gives every machine one turn, then checks whether the network has gone idle
+    pub fn step_round(&mut self) -> Result<Vec<NetworkEvent<W>>, NetworkError<W>> {
+        let mut events = Vec::new();
+        for address in 0..self.nics.len() {
+            self.run_machine(address, &mut events)?;
+        }
+
+        let idle = self
+            .nics
+            .iter()
+            .all(|nic| !nic.active && nic.incoming.is_empty());
+        if idle {
+            events.push(NetworkEvent::Idle);
+            if let Some(packet) = self.nat.clone().filter(|_| self.nat_enabled) {
+                let packet = Packet {
+                    destination: 0,
+                    ..packet
+                };
+                if self.send(packet.clone()) {
+                    events.push(NetworkEvent::NatDelivered(packet));
+                }
+            }
+        }
+        Ok(events)
+    }
+
+    /// Runs rounds until the predicate accepts one of the events, returning that event. Fails if
+    /// the network goes idle without any way to wake it up.
+    pub fn run_until(
+        &mut self,
+        mut predicate: impl FnMut(&NetworkEvent<W>) -> bool,
+    ) -> Result<NetworkEvent<W>, NetworkError<W>> {
+        loop {
+            let events = self.step_round()?;
+            let stalled = events.last() == Some(&NetworkEvent::Idle);
+            if let Some(event) = events.into_iter().find(|event| predicate(event)) {
+                return Ok(event);
+            }
+            if stalled {
+                return Err(NetworkError::Stalled);
+            }
+        }
+    }
+
+    fn run_machine(
+        &mut self,
+        address: usize,
+        events: &mut Vec<NetworkEvent<W>>,
+    ) -> Result<(), NetworkError<W>> {
+        let machine_error = |error| NetworkError::Machine { address, error };
+        self.nics[address].active = false;
+        let mut read_empty = false;
+        for _ in 0..self.time_slice {
+            let nic = &mut self.nics[address];
+            match nic.computer.step().map_err(machine_error)? {
+                StepResult::Executed(event) if event.output.is_some() => {
+                    nic.active = true;
+                    nic.outgoing.extend(nic.computer.take_output());
+                    if nic.outgoing.len() >= 3 {
+                        let words: Vec<W> = nic.outgoing.drain(..3).collect();
+                        if let [destination, x, y] = &words[..] {
+                            self.route(address, destination.clone(), x.clone(), y.clone(), events)?;
+                        }
+                    }
+                }
+                StepResult::Executed(_) => {}
+                StepResult::AwaitingInput => match nic.incoming.pop_front() {
+                    Some(packet) => {
+                        nic.active = true;
+                        nic.computer.push_input(packet.x);
+                        nic.computer.push_input(packet.y);
+                    }
+                    None if read_empty => return Ok(()),
+                    None => {
+                        read_empty = true;
+                        nic.computer.push_input(-W::one());
+                    }
+                },
+                StepResult::Halted => return Ok(()),
+            }
+        }
+        // a machine that used up its time slice may still be busy with something
+        self.nics[address].active = true;
+        Ok(())
+    }
+
+    fn route(
+        &mut self,
+        source: usize,
+        destination: W,
+        x: W,
+        y: W,
+        events: &mut Vec<NetworkEvent<W>>,
+    ) -> Result<(), NetworkError<W>> {
+        let destination = destination
+            .to_usize()
+            .ok_or(NetworkError::InvalidDestination { destination })?;
+        let packet = Packet { destination, x, y };
+        if self.nat_enabled && destination == NAT_ADDRESS {
+            self.nat = Some(packet.clone());
+            events.push(NetworkEvent::Sent { source, packet });
+        } else if self.send(packet.clone()) {
+            events.push(NetworkEvent::Sent { source, packet });
+        } else {
+            events.push(NetworkEvent::UnknownDestination {
+                source,
+                packet: packet.clone(),
+            });
+            let replacement = self
+                .unknown_destination
+                .as_mut()
+                .and_then(|hook| hook(source, packet));
+            if let Some(packet) = replacement {
+                if self.nat_enabled && packet.destination == NAT_ADDRESS {
+                    self.nat = Some(packet);
+                } else {
+                    self.send(packet);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::assembler::assemble;
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    // forwards every packet (x, y) to the next address as (x - 1, y + 1), dropping it once x
+    // reaches zero
+    const FORWARDER: &str = "
+                IN   [address]
+        wait:   IN   [x]
+                EQ   [x], #-1, [flag]
+                JNZ  [flag], #wait
+                IN   [y]
+                JZ   [x], #wait
+                ADD  [address], #1, [destination]
+                ADD  [x], #-1, [x]
+                ADD  [y], #1, [y]
+                OUT  [destination]
+                OUT  [x]
+                OUT  [y]
+                JZ   #0, #wait
+        address:     .data 0
+        destination: .data 0
+        x:           .data 0
+        y:           .data 0
+        flag:        .data 0
+    ";
+
+    fn setup_network(size: usize) -> Network {
+        let program = assemble(FORWARDER).expect("Failed to assemble");
+        let mut network = Network::new(program, size);
+        network.send(Packet {
+            destination: 0,
+            x: 5,
+            y: 0,
+        });
+        network
+    }
+
+    #[test]
+    fn it_should_route_packets_between_machines() {
+        let mut network = setup_network(4);
+        let mut sent = Vec::new();
+        let event = network
+            .run_until(|event| match event {
+                NetworkEvent::Sent { source, packet } => {
+                    sent.push((*source, packet.clone()));
+                    false
+                }
+                _ => true,
+            })
+            .expect("Failed to run network");
+        assert_eq!(
+            vec![
+                (
+                    0,
+                    Packet {
+                        destination: 1,
+                        x: 4,
+                        y: 1
+                    }
+                ),
+                (
+                    1,
+                    Packet {
+                        destination: 2,
+                        x: 3,
+                        y: 2
+                    }
+                ),
+                (
+                    2,
+                    Packet {
+                        destination: 3,
+                        x: 2,
+                        y: 3
+                    }
+                ),
+            ],
+            sent
+        );
+        assert_eq!(
+            NetworkEvent::UnknownDestination {
+                source: 3,
+                packet: Packet {
+                    destination: 4,
+                    x: 1,
+                    y: 4
+                }
+            },
+            event
+        );
+        assert_eq!(
+            Ok(NetworkEvent::Idle),
+            network.run_until(|event| event == &NetworkEvent::Idle)
+        );
+        assert_eq!(Err(NetworkError::Stalled), network.run_until(|_| false));
+    }
+
+    #[test]
+    fn it_should_wake_idle_networks_with_the_nat() {
+        let mut network = setup_network(3);
+        let unknown = Rc::new(RefCell::new(Vec::new()));
+        let recorded = unknown.clone();
+        network.on_unknown_destination(move |source, packet| {
+            recorded.borrow_mut().push(source);
+            Some(Packet {
+                destination: NAT_ADDRESS,
+                ..packet
+            })
+        });
+
+        let mut delivered = Vec::new();
+        network
+            .run_until(|event| match event {
+                NetworkEvent::NatDelivered(packet) => {
+                    let repeated = delivered.last() == Some(&packet.y);
+                    delivered.push(packet.y);
+                    repeated
+                }
+                _ => false,
+            })
+            .expect("Failed to run network");
+        assert_eq!(vec![3, 3], delivered);
+        assert_eq!(vec![2], *unknown.borrow());
+        assert_eq!(
+            Some(&Packet {
+                destination: NAT_ADDRESS,
+                x: 2,
+                y: 3
+            }),
+            network.nat_packet()
+        );
+    }
+
+    #[test]
+    fn it_should_yield_when_a_time_slice_is_used_up() {
+        // spins forever without asking for input
+        let program: Vec<i64> = assemble("loop: JZ #0, #loop").expect("Failed to assemble");
+        let mut network = Network::new(program, 2).with_time_slice(10);
+        assert_eq!(Ok(vec![]), network.step_round());
+        assert_eq!(10, network.machine(1).map_or(0, |machine| machine.steps()));
+    }
+
+    #[test]
+    fn it_should_report_machine_failures() {
+        let mut network = Network::new(vec![3, 0, 42], 2).without_nat();
+        assert_eq!(
+            Err(NetworkError::Machine {
+                address: 0,
+                error: ExecutionError::InvalidOperationCode { index: 2, code: 42 }
+            }),
+            network.step_round()
+        );
+    }
+}
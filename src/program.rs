@@ -0,0 +1,238 @@
+use thiserror::Error;
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Debug, Error)]
+pub enum ProgramError {
+    #[error("Unable to read the program: {0}")]
+    Io(#[from] io::Error),
+    #[error("The program does not contain any words")]
+    Empty,
+    #[error("Missing word at index {index} (byte offset {offset})")]
+    MissingWord { index: usize, offset: usize },
+    #[error("Invalid word {token:?} at index {index} (byte offset {offset})")]
+    InvalidWord {
+        index: usize,
+        offset: usize,
+        token: String,
+    },
+    #[error("Word {token} at index {index} (byte offset {offset}) is out of range")]
+    OutOfRange {
+        index: usize,
+        offset: usize,
+        token: String,
+    },
+}
+
+/// An Intcode program, parsed from comma separated words. Whitespace and newlines around the
+/// words are ignored, as is anything from a `;` to the end of its line.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Program<W = i64> {
+    words: Vec<W>,
+}
+
+impl<W: FromStr> Program<W> {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ProgramError> {
+        std::fs::read_to_string(path)?.parse()
+    }
+
+    pub fn from_reader(mut reader: impl io::Read) -> Result<Self, ProgramError> {
+        let mut source = String::new();
+        reader.read_to_string(&mut source)?;
+        source.parse()
+    }
+}
+
+impl<W> Program<W> {
+    pub fn words(&self) -> &[W] {
+        &self.words
+    }
+
+    pub fn into_words(self) -> Vec<W> {
+        self.words
+    }
+}
+
+impl<W> From<Vec<W>> for Program<W> {
+    fn from(words: Vec<W>) -> Self {
+        Program { words }
+    }
+}
+
+impl<W> From<Program<W>> for Vec<W> {
+    fn from(program: Program<W>) -> Self {
+        program.words
+    }
+}
+
+impl<W: FromStr> FromStr for Program<W> {
+    type Err = ProgramError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let source = without_comments(source);
+        if source.trim().is_empty() {
+            return Err(ProgramError::Empty);
+        }
+
+        let mut words = Vec::new();
+        let mut offset = 0;
+        for (index, piece) in source.split(',').enumerate() {
+            let token_offset = offset + (piece.len() - piece.trim_start().len());
+            offset += piece.len() + 1;
+
+            let token = piece.trim();
+            if token.is_empty() {
+                return Err(ProgramError::MissingWord {
+                    index,
+                    offset: token_offset,
+                });
+            }
+            let word = token.parse().map_err(|_| {
+                let (index, offset, token) = (index, token_offset, token.to_string());
+                if is_integer(&token) {
+                    ProgramError::OutOfRange {
+                        index,
+                        offset,
+                        token,
+                    }
+                } else {
+                    ProgramError::InvalidWord {
+                        index,
+                        offset,
+                        token,
+                    }
+                }
+            })?;
+            words.push(word);
+        }
+        Ok(Program { words })
+    }
+}
+
+impl<W: fmt::Display> fmt::Display for Program<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, word) in self.words.iter().enumerate() {
+            if index > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", word)?;
+        }
+        Ok(())
+    }
+}
+
+/// Blanks out comments with spaces, so that byte offsets into the result match the original
+fn without_comments(source: &str) -> String {
+    source
+        .split_inclusive('\n')
+        .map(|line| match line.find(';') {
+            Some(start) => {
+                let end = line.trim_end_matches(&['\r', '\n'][..]).len();
+                format!(
+                    "{}{}{}",
+                    &line[..start],
+                    " ".repeat(end - start),
+                    &line[end..]
+                )
+            }
+            None => line.to_string(),
+        })
+        .collect()
+}
+
+fn is_integer(token: &str) -> bool {
+    let digits = token.strip_prefix(&['+', '-'][..]).unwrap_or(token);
+    !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_parse_a_program() {
+        let program: Program = "1,0,0,3,99\n".parse().expect("Failed to parse");
+        assert_eq!(&[1, 0, 0, 3, 99], program.words());
+        assert_eq!("1,0,0,3,99", program.to_string());
+    }
+
+    #[test]
+    fn it_should_ignore_whitespace_and_comments() {
+        let source = "; adds two numbers\n1, 0,0, ; the operands\r\n  3,\n\t-99 ; the end\n";
+        let program: Program = source.parse().expect("Failed to parse");
+        assert_eq!(vec![1, 0, 0, 3, -99], program.into_words());
+    }
+
+    #[test]
+    fn it_should_report_invalid_words() {
+        match "1,0,x0,3".parse::<Program>() {
+            Err(ProgramError::InvalidWord {
+                index: 2,
+                offset: 4,
+                token,
+            }) => assert_eq!("x0", token),
+            result => panic!("Unexpected result {:?}", result),
+        }
+        match "4,\n 2 3 ; missing a comma".parse::<Program>() {
+            Err(ProgramError::InvalidWord {
+                index: 1,
+                offset: 4,
+                token,
+            }) => assert_eq!("2 3", token),
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn it_should_report_out_of_range_words() {
+        match "1, 5000000000".parse::<Program<i32>>() {
+            Err(ProgramError::OutOfRange {
+                index: 1,
+                offset: 3,
+                token,
+            }) => assert_eq!("5000000000", token),
+            result => panic!("Unexpected result {:?}", result),
+        }
+        assert!(matches!(
+            "1,-1".parse::<Program<u32>>(),
+            Err(ProgramError::OutOfRange { index: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn it_should_report_missing_words() {
+        assert!(matches!(
+            "1,,2".parse::<Program>(),
+            Err(ProgramError::MissingWord {
+                index: 1,
+                offset: 2
+            })
+        ));
+        assert!(matches!(
+            "1,2,\n".parse::<Program>(),
+            Err(ProgramError::MissingWord {
+                index: 2,
+                offset: 5
+            })
+        ));
+        assert!(matches!(
+            " \n; nothing here\n".parse::<Program>(),
+            Err(ProgramError::Empty)
+        ));
+    }
+
+    #[test]
+    fn it_should_load_the_puzzle_inputs() {
+        let program: Program =
+            Program::from_file("data/day-five-input.txt").expect("Failed to load");
+        assert_eq!(678, program.words().len());
+        assert_eq!(Some(&226), program.words().last());
+        assert!(matches!(
+            Program::<i64>::from_file("data/missing.txt"),
+            Err(ProgramError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,19 @@
+/// The day seven feedback loop example, which reads its phase and then feeds signals through
+pub const FEEDBACK_AMPLIFIER: [i64; 29] = [
+    3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28, 1005,
+    28, 6, 99, 0, 0, 5,
+];
+
+/// Reads a number and outputs it doubled until it reads a zero
+pub const DOUBLER: &str = "
+    loop:   IN   [value]
+            JZ   [value], #end
+            MUL  [value], #2, [value]
+            OUT  [value]
+            JZ   #0, #loop
+    end:    HLT
+    value:  .data 0
+";
+
+/// Counts down from 100000 in a loop of two instructions
+pub const COUNT_DOWN: [i64; 9] = [1001, 8, -1, 8, 1005, 8, 0, 99, 100_000];
@@ -0,0 +1,410 @@
+use thiserror::Error;
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::intcode_computer::{
+    ExecutionError, ExecutionState, InputSource, IntcodeComputer, OutputSink, Word,
+};
+
+/// How long a machine in a cluster waits for input before checking whether it has been stopped
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Debug, PartialEq, Error)]
+pub enum ThreadError<W: Word = i64> {
+    #[error("There is no machine {machine}")]
+    UnknownMachine { machine: usize },
+    #[error("Machine {machine} failed: {error}")]
+    Machine {
+        machine: usize,
+        error: ExecutionError<W>,
+    },
+    #[error("The thread for machine {machine} panicked")]
+    Panicked { machine: usize },
+}
+
+/// Sent to the coordinator as each machine's thread finishes
+#[derive(Debug, PartialEq, Clone)]
+pub enum MachineEvent<W: Word = i64> {
+    Halted(usize),
+    /// The machine wanted input after every sender to its input channel had been dropped
+    InputClosed(usize),
+    /// The machine was waiting for input when the coordinator stopped the cluster
+    Stopped(usize),
+    Failed {
+        machine: usize,
+        error: ExecutionError<W>,
+    },
+}
+
+/// A machine running on its own thread
+pub struct MachineHandle<W: Word = i64> {
+    machine: usize,
+    thread: JoinHandle<Result<IntcodeComputer<W>, ThreadError<W>>>,
+}
+
+impl<W: Word> MachineHandle<W> {
+    /// Waits for the machine to stop, returning it in its final state
+    pub fn join(self) -> Result<IntcodeComputer<W>, ThreadError<W>> {
+        let machine = self.machine;
+        self.thread
+            .join()
+            .map_err(|_| ThreadError::Panicked { machine })?
+    }
+}
+
+/// Runs the computer on a new thread, blocking on the channel whenever it needs input. The
+/// thread finishes when the program halts or when every sender to its input has been dropped.
+pub fn spawn<W: Word + Send + Sync + 'static>(
+    computer: IntcodeComputer<W>,
+    input: Receiver<W>,
+    output: Sender<W>,
+) -> MachineHandle<W> {
+    let input = MachineInput {
+        receiver: input,
+        stop: None,
+    };
+    spawn_machine(0, computer, input, output, None)
+}
+
+/// A machine's input channel, which gives up waiting once the stop signal is raised
+struct MachineInput<W> {
+    receiver: Receiver<W>,
+    stop: Option<Arc<AtomicBool>>,
+}
+
+impl<W> MachineInput<W> {
+    fn is_stopped(&self) -> bool {
+        self.stop
+            .as_ref()
+            .is_some_and(|stop| stop.load(Ordering::SeqCst))
+    }
+}
+
+impl<W> InputSource<W> for MachineInput<W> {
+    fn next_input(&mut self) -> io::Result<Option<W>> {
+        if self.stop.is_none() {
+            return self.receiver.next_input();
+        }
+        loop {
+            match self.receiver.recv_timeout(STOP_POLL_INTERVAL) {
+                Ok(value) => return Ok(Some(value)),
+                Err(RecvTimeoutError::Disconnected) => return Ok(None),
+                Err(RecvTimeoutError::Timeout) if self.is_stopped() => return Ok(None),
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+        }
+    }
+}
+
+fn spawn_machine<W: Word + Send + Sync + 'static>(
+    machine: usize,
+    mut computer: IntcodeComputer<W>,
+    mut input: MachineInput<W>,
+    mut output: impl OutputSink<W> + Send + 'static,
+    events: Option<Sender<MachineEvent<W>>>,
+) -> MachineHandle<W> {
+    let thread = thread::spawn(move || {
+        let result = computer.run_with(&mut input, &mut output);
+        let stopped = input.is_stopped();
+        // let the neighbours see the disconnect before the coordinator hears about it
+        drop(input);
+        drop(output);
+
+        let event = match &result {
+            Ok(ExecutionState::Halted) => MachineEvent::Halted(machine),
+            Ok(_) if stopped => MachineEvent::Stopped(machine),
+            Ok(_) => MachineEvent::InputClosed(machine),
+            Err(error) => MachineEvent::Failed {
+                machine,
+                error: error.clone(),
+            },
+        };
+        if let Some(events) = events {
+            // the coordinator is allowed to stop listening
+            let _ = events.send(event);
+        }
+        result
+            .map(|_| computer)
+            .map_err(|error| ThreadError::Machine { machine, error })
+    });
+    MachineHandle { machine, thread }
+}
+
+/// Copies every output to each connected machine, skipping any that have already stopped
+struct Fanout<W> {
+    targets: Vec<Sender<W>>,
+    tap: Option<Sender<W>>,
+}
+
+impl<W: Clone> OutputSink<W> for Fanout<W> {
+    fn send_output(&mut self, value: W) -> io::Result<()> {
+        self.targets
+            .retain(|target| target.send(value.clone()).is_ok());
+        if let Some(tap) = &self.tap {
+            let _ = tap.send(value);
+        }
+        Ok(())
+    }
+}
+
+struct PendingMachine<W: Word> {
+    computer: IntcodeComputer<W>,
+    sender: Sender<W>,
+    receiver: Receiver<W>,
+    targets: Vec<usize>,
+    tap: Option<Sender<W>>,
+}
+
+/// A set of machines that each run on their own thread once spawned, with their outputs
+/// connected to the inputs of other machines over channels
+pub struct Cluster<W: Word = i64> {
+    machines: Vec<PendingMachine<W>>,
+}
+
+impl<W: Word + Send + Sync + 'static> Default for Cluster<W> {
+    fn default() -> Self {
+        Cluster {
+            machines: Vec::new(),
+        }
+    }
+}
+
+impl<W: Word + Send + Sync + 'static> Cluster<W> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a machine, which starts with whatever input is already queued on the computer
+    pub fn add_machine(&mut self, computer: IntcodeComputer<W>) -> usize {
+        let (sender, receiver) = channel();
+        self.machines.push(PendingMachine {
+            computer,
+            sender,
+            receiver,
+            targets: Vec::new(),
+            tap: None,
+        });
+        self.machines.len() - 1
+    }
+
+    pub fn connect(&mut self, from: usize, to: usize) -> Result<(), ThreadError<W>> {
+        self.check_machine(to)?;
+        self.check_machine(from)?;
+        self.machines[from].targets.push(to);
+        Ok(())
+    }
+
+    /// A sender for feeding the machine input from outside of the cluster. The machine can not
+    /// shut down for lack of input while the sender is alive.
+    pub fn input(&self, machine: usize) -> Result<Sender<W>, ThreadError<W>> {
+        self.check_machine(machine)?;
+        Ok(self.machines[machine].sender.clone())
+    }
+
+    /// A receiver for a copy of everything that the machine outputs
+    pub fn output(&mut self, machine: usize) -> Result<Receiver<W>, ThreadError<W>> {
+        self.check_machine(machine)?;
+        let (sender, receiver) = channel();
+        self.machines[machine].tap = Some(sender);
+        Ok(receiver)
+    }
+
+    /// Starts every machine on its own thread. A machine only shuts down for lack of input once
+    /// nothing can send to it, so a ring or a machine connected to itself keeps waiting forever
+    /// unless the coordinator calls [`RunningCluster::stop`].
+    pub fn spawn(self) -> RunningCluster<W> {
+        let senders: Vec<Sender<W>> = self
+            .machines
+            .iter()
+            .map(|machine| machine.sender.clone())
+            .collect();
+        let (events, coordinator) = channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let handles = self
+            .machines
+            .into_iter()
+            .enumerate()
+            .map(|(index, machine)| {
+                let output = Fanout {
+                    targets: machine
+                        .targets
+                        .iter()
+                        .map(|&target| senders[target].clone())
+                        .collect(),
+                    tap: machine.tap,
+                };
+                let input = MachineInput {
+                    receiver: machine.receiver,
+                    stop: Some(Arc::clone(&stop)),
+                };
+                spawn_machine(index, machine.computer, input, output, Some(events.clone()))
+            })
+            .collect();
+        RunningCluster {
+            handles,
+            events: coordinator,
+            stop,
+        }
+    }
+
+    fn check_machine(&self, machine: usize) -> Result<(), ThreadError<W>> {
+        if machine < self.machines.len() {
+            Ok(())
+        } else {
+            Err(ThreadError::UnknownMachine { machine })
+        }
+    }
+}
+
+pub struct RunningCluster<W: Word = i64> {
+    handles: Vec<MachineHandle<W>>,
+    events: Receiver<MachineEvent<W>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl<W: Word> RunningCluster<W> {
+    /// Blocks until another machine stops, or returns `None` once all of them have
+    pub fn next_event(&self) -> Option<MachineEvent<W>> {
+        self.events.recv().ok()
+    }
+
+    /// Stops every machine the next time that it waits for an empty input channel, so that
+    /// machines waiting on each other can shut down. Input that is already queued still runs.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+
+    /// Waits for every machine to stop, returning them in their final states, or the error of
+    /// the lowest numbered machine that failed, regardless of which failed first
+    pub fn join(self) -> Result<Vec<IntcodeComputer<W>>, ThreadError<W>> {
+        let results: Vec<_> = self.handles.into_iter().map(MachineHandle::join).collect();
+        results.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::assembler::assemble;
+    use crate::test_programs::{DOUBLER, FEEDBACK_AMPLIFIER};
+
+    #[test]
+    fn it_should_block_on_input_from_a_channel() {
+        let program: Vec<i64> = assemble(DOUBLER).expect("Failed to assemble");
+        let (input, receiver) = channel();
+        let (sender, output) = channel();
+        let handle = spawn(IntcodeComputer::new(program), receiver, sender);
+
+        input.send(3).expect("Failed to send");
+        assert_eq!(Ok(6), output.recv());
+        input.send(-5).expect("Failed to send");
+        assert_eq!(Ok(-10), output.recv());
+
+        drop(input);
+        let computer = handle.join().expect("Failed to join");
+        assert!(!computer.is_halted());
+        assert_eq!(10, computer.steps());
+        assert!(output.recv().is_err());
+    }
+
+    #[test]
+    fn it_should_run_a_ring_of_machines() {
+        let mut cluster = Cluster::new();
+        for (index, &phase) in [9, 8, 7, 6, 5].iter().enumerate() {
+            let mut computer = IntcodeComputer::new(FEEDBACK_AMPLIFIER.to_vec());
+            computer.push_input(phase);
+            if index == 0 {
+                computer.push_input(0);
+            }
+            cluster.add_machine(computer);
+        }
+        for machine in 0..5 {
+            cluster
+                .connect(machine, (machine + 1) % 5)
+                .expect("Failed to connect");
+        }
+        let thrusters = cluster.output(4).expect("Failed to tap output");
+
+        let running = cluster.spawn();
+        let mut halted: Vec<_> = (0..5).filter_map(|_| running.next_event()).collect();
+        halted.sort_by_key(|event| match event {
+            MachineEvent::Halted(machine) => *machine,
+            _ => usize::MAX,
+        });
+        assert_eq!((0..5).map(MachineEvent::Halted).collect::<Vec<_>>(), halted);
+        assert_eq!(None, running.next_event());
+
+        let computers = running.join().expect("Failed to join");
+        assert!(computers.iter().all(|computer| computer.is_halted()));
+        assert_eq!(Some(139629729), thrusters.iter().last());
+    }
+
+    #[test]
+    fn it_should_stop_machines_that_wait_on_each_other() {
+        let program: Vec<i64> = assemble(DOUBLER).expect("Failed to assemble");
+        let mut cluster = Cluster::new();
+        let first = cluster.add_machine(IntcodeComputer::new(program.clone()));
+        let second = cluster.add_machine(IntcodeComputer::new(program.clone()));
+        let looped = cluster.add_machine(IntcodeComputer::new(program));
+        cluster.connect(first, second).expect("Failed to connect");
+        cluster.connect(second, first).expect("Failed to connect");
+        cluster.connect(looped, looped).expect("Failed to connect");
+
+        let running = cluster.spawn();
+        running.stop();
+        let mut events: Vec<_> = (0..3).filter_map(|_| running.next_event()).collect();
+        events.sort_by_key(|event| match event {
+            MachineEvent::Stopped(machine) => *machine,
+            _ => usize::MAX,
+        });
+        assert_eq!(
+            vec![
+                MachineEvent::Stopped(first),
+                MachineEvent::Stopped(second),
+                MachineEvent::Stopped(looped)
+            ],
+            events
+        );
+
+        let computers = running.join().expect("Failed to join");
+        assert!(computers.iter().all(|computer| !computer.is_halted()));
+    }
+
+    #[test]
+    fn it_should_report_failures_to_the_coordinator() {
+        let program: Vec<i64> = assemble(DOUBLER).expect("Failed to assemble");
+        let mut cluster = Cluster::new();
+        let broken = cluster.add_machine(IntcodeComputer::new(vec![104, 1, 42]));
+        let doubler = cluster.add_machine(IntcodeComputer::new(program));
+        cluster.connect(broken, doubler).expect("Failed to connect");
+        let doubled = cluster.output(doubler).expect("Failed to tap output");
+        assert_eq!(
+            Err(ThreadError::UnknownMachine { machine: 2 }),
+            cluster.connect(doubler, 2).map(|_| ())
+        );
+
+        let running = cluster.spawn();
+        let error = ExecutionError::InvalidOperationCode { index: 2, code: 42 };
+        // the threads race each other to report, so the order of the events is not fixed
+        let events: Vec<_> = (0..2).filter_map(|_| running.next_event()).collect();
+        assert!(events.contains(&MachineEvent::Failed {
+            machine: broken,
+            error: error.clone()
+        }));
+        assert!(events.contains(&MachineEvent::InputClosed(doubler)));
+        assert_eq!(
+            Err(ThreadError::Machine {
+                machine: broken,
+                error
+            }),
+            running.join().map(|computers| computers.len())
+        );
+        assert_eq!(vec![2], doubled.iter().collect::<Vec<_>>());
+    }
+}
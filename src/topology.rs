@@ -0,0 +1,297 @@
+use thiserror::Error;
+
+use crate::intcode_computer::{ExecutionError, IntcodeComputer, StepResult, Word};
+use crate::network::DEFAULT_TIME_SLICE;
+
+#[derive(Debug, PartialEq, Error)]
+pub enum TopologyError<W: Word = i64> {
+    #[error("There is no machine {node} in the topology")]
+    UnknownNode { node: usize },
+    #[error("Machine {node} failed: {error}")]
+    Machine {
+        node: usize,
+        error: ExecutionError<W>,
+    },
+    #[error("Deadlock: machines {blocked:?} are all waiting for input")]
+    Deadlock { blocked: Vec<usize> },
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TopologyReport<W: Word = i64> {
+    /// Every word that each machine produced, indexed by node
+    pub outputs: Vec<Vec<W>>,
+    /// The nodes that have halted, in the order that they halted
+    pub halting_order: Vec<usize>,
+}
+
+struct Node<W: Word> {
+    computer: IntcodeComputer<W>,
+    targets: Vec<usize>,
+    outputs: Vec<W>,
+    halted: bool,
+}
+
+/// A set of Intcode machines whose outputs are connected to the inputs of other machines. A
+/// machine with several connections sends a copy of every output word down each of them, and a
+/// machine that several others are connected to reads their words in the order they were sent.
+/// The machines are scheduled round robin, each running until it needs input that has not
+/// arrived, halts, or uses up its time slice.
+pub struct Topology<W: Word = i64> {
+    nodes: Vec<Node<W>>,
+    halting_order: Vec<usize>,
+    time_slice: usize,
+}
+
+impl<W: Word> Default for Topology<W> {
+    fn default() -> Self {
+        Topology {
+            nodes: Vec::new(),
+            halting_order: Vec::new(),
+            time_slice: DEFAULT_TIME_SLICE,
+        }
+    }
+}
+
+impl<W: Word> Topology<W> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A copy of the program for each seed, with every machine connected to the next one
+    pub fn pipeline(program: Vec<W>, seeds: Vec<Option<W>>) -> Self {
+        let mut topology = Self::new();
+        for seed in seeds {
+            let node = topology.add_machine(program.clone(), seed);
+            if node > 0 {
+                topology.nodes[node - 1].targets.push(node);
+            }
+        }
+        topology
+    }
+
+    /// A pipeline whose last machine is connected back to its first one
+    pub fn ring(program: Vec<W>, seeds: Vec<Option<W>>) -> Self {
+        let mut topology = Self::pipeline(program, seeds);
+        if let Some(last) = topology.nodes.last_mut() {
+            last.targets.push(0);
+        }
+        topology
+    }
+
+    pub fn with_time_slice(mut self, time_slice: usize) -> Self {
+        self.time_slice = time_slice.max(1);
+        self
+    }
+
+    /// Adds a machine running the program, starting with the seed as its first input, and
+    /// returns its node
+    pub fn add_machine(&mut self, program: Vec<W>, seed: Option<W>) -> usize {
+        let mut computer = IntcodeComputer::new(program);
+        if let Some(seed) = seed {
+            computer.push_input(seed);
+        }
+        self.add_computer(computer)
+    }
+
+    pub fn add_computer(&mut self, computer: IntcodeComputer<W>) -> usize {
+        self.nodes.push(Node {
+            computer,
+            targets: Vec::new(),
+            outputs: Vec::new(),
+            halted: false,
+        });
+        self.nodes.len() - 1
+    }
+
+    /// Sends every word that the `from` machine outputs to the input of the `to` machine
+    pub fn connect(&mut self, from: usize, to: usize) -> Result<(), TopologyError<W>> {
+        self.check_node(to)?;
+        self.check_node(from)?;
+        self.nodes[from].targets.push(to);
+        Ok(())
+    }
+
+    pub fn push_input(&mut self, node: usize, value: W) -> Result<(), TopologyError<W>> {
+        self.check_node(node)?;
+        self.nodes[node].computer.push_input(value);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn machine(&self, node: usize) -> Option<&IntcodeComputer<W>> {
+        self.nodes.get(node).map(|node| &node.computer)
+    }
+
+    pub fn report(&self) -> TopologyReport<W> {
+        TopologyReport {
+            outputs: self.nodes.iter().map(|node| node.outputs.clone()).collect(),
+            halting_order: self.halting_order.clone(),
+        }
+    }
+
+    /// Runs the machines until all of them have halted. Fails with a deadlock if the machines
+    /// that are still running are all waiting for input that will never arrive; the outputs up
+    /// to that point are still available from `report`.
+    pub fn run(&mut self) -> Result<TopologyReport<W>, TopologyError<W>> {
+        loop {
+            let mut progressed = false;
+            for node in 0..self.nodes.len() {
+                progressed |= self.run_machine(node)?;
+            }
+
+            let blocked: Vec<usize> = (0..self.nodes.len())
+                .filter(|&node| !self.nodes[node].halted)
+                .collect();
+            if blocked.is_empty() {
+                return Ok(self.report());
+            }
+            if !progressed {
+                return Err(TopologyError::Deadlock { blocked });
+            }
+        }
+    }
+
+    /// Runs a single machine for up to a time slice, returning whether it executed anything
+    fn run_machine(&mut self, node: usize) -> Result<bool, TopologyError<W>> {
+        let mut executed = false;
+        for _ in 0..self.time_slice {
+            let step = self.nodes[node]
+                .computer
+                .step()
+                .map_err(|error| TopologyError::Machine { node, error })?;
+            match step {
+                StepResult::Executed(event) => {
+                    executed = true;
+                    if event.output.is_some() {
+                        self.deliver_output(node);
+                    }
+                }
+                StepResult::AwaitingInput => break,
+                StepResult::Halted => {
+                    if !self.nodes[node].halted {
+                        self.nodes[node].halted = true;
+                        self.halting_order.push(node);
+                        executed = true;
+                    }
+                    break;
+                }
+            }
+        }
+        Ok(executed)
+    }
+
+    fn deliver_output(&mut self, node: usize) {
+        let words = self.nodes[node].computer.take_output();
+        let targets = self.nodes[node].targets.clone();
+        for word in words {
+            for &target in &targets {
+                self.nodes[target].computer.push_input(word.clone());
+            }
+            self.nodes[node].outputs.push(word);
+        }
+    }
+
+    fn check_node(&self, node: usize) -> Result<(), TopologyError<W>> {
+        if node < self.nodes.len() {
+            Ok(())
+        } else {
+            Err(TopologyError::UnknownNode { node })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::assembler::assemble;
+    use crate::test_programs::{DOUBLER, FEEDBACK_AMPLIFIER};
+
+    #[test]
+    fn it_should_run_pipelines() {
+        let program: Vec<i64> = vec![3, 9, 1002, 9, 3, 9, 4, 9, 99, 0];
+        let mut topology = Topology::pipeline(program, vec![Some(1), None, None]);
+        assert_eq!(
+            Ok(TopologyReport {
+                outputs: vec![vec![3], vec![9], vec![27]],
+                halting_order: vec![0, 1, 2],
+            }),
+            topology.run()
+        );
+    }
+
+    #[test]
+    fn it_should_run_rings() {
+        let seeds = [9, 8, 7, 6, 5].iter().map(|&phase| Some(phase)).collect();
+        let mut topology = Topology::ring(FEEDBACK_AMPLIFIER.to_vec(), seeds);
+        topology.push_input(0, 0).expect("Failed to seed the ring");
+        let report = topology.run().expect("Failed to run ring");
+        assert_eq!(Some(&139629729), report.outputs[4].last());
+        assert_eq!(vec![0, 1, 2, 3, 4], report.halting_order);
+    }
+
+    #[test]
+    fn it_should_fan_out_and_in() {
+        let doubler: Vec<i64> = assemble(DOUBLER).expect("Failed to assemble");
+        let mut topology = Topology::new();
+        let source = topology.add_machine(doubler.clone(), None);
+        let left = topology.add_machine(doubler.clone(), None);
+        let right = topology.add_machine(doubler.clone(), None);
+        let sink = topology.add_machine(doubler, None);
+        for &(from, to) in [(source, left), (source, right), (left, sink), (right, sink)].iter() {
+            topology.connect(from, to).expect("Failed to connect");
+        }
+        topology
+            .push_input(source, 1)
+            .expect("Failed to push input");
+        topology
+            .push_input(source, 0)
+            .expect("Failed to push input");
+
+        assert_eq!(
+            Err(TopologyError::Deadlock {
+                blocked: vec![left, right, sink]
+            }),
+            topology.run()
+        );
+        assert_eq!(
+            TopologyReport {
+                outputs: vec![vec![2], vec![4], vec![4], vec![8, 8]],
+                halting_order: vec![source],
+            },
+            topology.report()
+        );
+
+        // the source never sends the zero that would stop the others
+        for &node in [left, right, sink].iter() {
+            topology.push_input(node, 0).expect("Failed to push input");
+        }
+        assert_eq!(
+            vec![source, left, right, sink],
+            topology.run().expect("Failed to run").halting_order
+        );
+        assert_eq!(
+            Err(TopologyError::UnknownNode { node: 4 }),
+            topology.connect(sink, 4)
+        );
+    }
+
+    #[test]
+    fn it_should_report_machine_failures() {
+        let mut topology = Topology::pipeline(vec![104, 1, 42], vec![None, None]);
+        assert_eq!(
+            Err(TopologyError::Machine {
+                node: 0,
+                error: ExecutionError::InvalidOperationCode { index: 2, code: 42 }
+            }),
+            topology.run()
+        );
+    }
+}
@@ -0,0 +1,881 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::disassembler::Instruction;
+use crate::intcode_computer::{Operation, ParameterMode};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Language {
+    C,
+    Rust,
+}
+
+impl FromStr for Language {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "c" => Ok(Language::C),
+            "rust" | "rs" => Ok(Language::Rust),
+            _ => Err(format!("Unknown language {}", value)),
+        }
+    }
+}
+
+impl Language {
+    /// What the generated code puts in front of the machine's state and helpers
+    fn machine(self) -> &'static str {
+        match self {
+            Language::C => "",
+            Language::Rust => "machine.",
+        }
+    }
+
+    fn literal(self, value: i64) -> String {
+        match self {
+            Language::C if value == i64::MIN => String::from("INT64_MIN"),
+            Language::Rust if value == i64::MIN => String::from("i64::MIN"),
+            _ => value.to_string(),
+        }
+    }
+
+    fn relative(self, offset: i64) -> String {
+        if offset < 0 && offset != i64::MIN {
+            format!("{}relative_base - {}", self.machine(), -offset)
+        } else {
+            format!("{}relative_base + {}", self.machine(), self.literal(offset))
+        }
+    }
+
+    fn value(self, mode: ParameterMode, parameter: i64) -> String {
+        match mode {
+            ParameterMode::Position => {
+                format!("{}load({})", self.machine(), self.literal(parameter))
+            }
+            ParameterMode::Immediate => self.literal(parameter),
+            ParameterMode::Relative => {
+                format!("{}load({})", self.machine(), self.relative(parameter))
+            }
+        }
+    }
+
+    fn address(self, mode: ParameterMode, parameter: i64) -> String {
+        match mode {
+            ParameterMode::Relative => self.relative(parameter),
+            _ => self.literal(parameter),
+        }
+    }
+
+    fn boolean(self, expression: String) -> String {
+        match self {
+            Language::C => expression,
+            Language::Rust => format!("i64::from({})", expression),
+        }
+    }
+}
+
+/// Turns a program into a standalone C or Rust source file that reads its input from stdin, as
+/// integers of up to 31 characters separated by commas or whitespace, and writes each output to
+/// stdout on its own line.
+///
+/// Every address that holds a valid instruction becomes a case of a `switch`/`match` on the
+/// program counter, with the operands of the instruction compiled in. Jumps to immediate targets
+/// go straight to the case in C, while Rust has no `goto`, so they go back through the dispatch
+/// loop. Writing over a transpiled instruction marks it dirty, and from then on it runs through
+/// the interpreter that the generated code carries for that, as do addresses that were not valid
+/// instructions when the program was transpiled.
+pub fn transpile(program: &[i64], language: Language) -> String {
+    let instructions: BTreeMap<usize, Instruction> = (0..program.len())
+        .filter_map(|address| Instruction::decode(program, address))
+        .map(|instruction| (instruction.address, instruction))
+        .collect();
+    let mut source = String::new();
+    match language {
+        Language::C => write_c(&mut source, program, &instructions),
+        Language::Rust => write_rust(&mut source, program, &instructions),
+    }
+    .expect("Writing to a string does not fail");
+    source
+}
+
+/// The values of an array literal, sixteen to a line
+fn array_values(values: impl Iterator<Item = String>) -> String {
+    let values: Vec<String> = values.collect();
+    values
+        .chunks(16)
+        .map(|chunk| format!("    {},\n", chunk.join(", ")))
+        .collect()
+}
+
+fn image(program: &[i64], language: Language) -> String {
+    array_values(program.iter().map(|&value| language.literal(value)))
+}
+
+/// The number of cells that the transpiled instruction at each address covers, or zero
+fn lengths(program: &[i64], instructions: &BTreeMap<usize, Instruction>) -> String {
+    array_values((0..program.len()).map(|address| {
+        instructions
+            .get(&address)
+            .map_or(0, |instruction| 1 + instruction.parameters.len())
+            .to_string()
+    }))
+}
+
+/// The statements of an instruction that does not change the flow of control
+fn statements(instruction: &Instruction, language: Language) -> Vec<String> {
+    let modes = instruction.operation.parameter_modes();
+    let value = |index: usize| language.value(modes[index], instruction.parameters[index]);
+    let address = |index: usize| language.address(modes[index], instruction.parameters[index]);
+    let store = |value: String| format!("{}store({}, {});", language.machine(), address(2), value);
+    match instruction.operation {
+        Operation::Add(_, _, _) => vec![store(format!("{} + {}", value(0), value(1)))],
+        Operation::Multiply(_, _, _) => vec![store(format!("{} * {}", value(0), value(1)))],
+        Operation::LessThan(_, _, _) => {
+            vec![store(language.boolean(format!(
+                "{} < {}",
+                value(0),
+                value(1)
+            )))]
+        }
+        Operation::EqualTo(_, _, _) => {
+            vec![store(language.boolean(format!(
+                "{} == {}",
+                value(0),
+                value(1)
+            )))]
+        }
+        Operation::StoreInput(_) => match language {
+            Language::C => vec![format!(
+                "store({}, read_input({}));",
+                address(0),
+                instruction.address
+            )],
+            Language::Rust => vec![
+                format!("let input = machine.read_input({});", instruction.address),
+                format!("machine.store({}, input);", address(0)),
+            ],
+        },
+        Operation::ProduceOutput(_) => vec![format!("write_output({});", value(0))],
+        Operation::AdjustRelativeBase(_) => {
+            vec![format!(
+                "{}relative_base += {};",
+                language.machine(),
+                value(0)
+            )]
+        }
+        Operation::JumpIfTrue(_, _) | Operation::JumpIfFalse(_, _) | Operation::Terminate => {
+            Vec::new()
+        }
+    }
+}
+
+/// The condition under which a jump is taken
+fn condition(instruction: &Instruction, language: Language) -> String {
+    let modes = instruction.operation.parameter_modes();
+    let value = language.value(modes[0], instruction.parameters[0]);
+    match instruction.operation {
+        Operation::JumpIfTrue(_, _) => format!("{} != 0", value),
+        _ => format!("{} == 0", value),
+    }
+}
+
+/// Where a jump goes, as a literal address when it is known up front or otherwise as a call that
+/// checks the one that it reads from memory
+fn jump_destination(instruction: &Instruction, language: Language) -> String {
+    match instruction.jump_target() {
+        Some(target) => target.to_string(),
+        None => {
+            let modes = instruction.operation.parameter_modes();
+            format!(
+                "{}jump({}, {})",
+                language.machine(),
+                language.value(modes[1], instruction.parameters[1]),
+                instruction.address
+            )
+        }
+    }
+}
+
+const C_PRELUDE: &str = "#include <ctype.h>
+#include <errno.h>
+#include <stdarg.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+";
+
+const C_RUNTIME: &str = r#"static const int64_t PLACES[] = {100, 1000, 10000};
+
+static int64_t *memory;
+static size_t memory_length;
+static unsigned char dirty[sizeof LENGTHS];
+static int64_t relative_base;
+
+static _Noreturn void fail(const char *format, ...) {
+    va_list arguments;
+    fflush(stdout);
+    va_start(arguments, format);
+    vfprintf(stderr, format, arguments);
+    va_end(arguments);
+    fputc('\n', stderr);
+    exit(1);
+}
+
+static int64_t load(int64_t address) {
+    if (address < 0) fail("Invalid address %lld", (long long) address);
+    return (uint64_t) address < memory_length ? memory[address] : 0;
+}
+
+static void store(int64_t address, int64_t value) {
+    if (address < 0) fail("Invalid address %lld", (long long) address);
+    if ((uint64_t) address >= memory_length) {
+        size_t length = memory_length * 2 > (size_t) address ? memory_length * 2 : (size_t) address + 1;
+        memory = realloc(memory, length * sizeof *memory);
+        if (memory == NULL) fail("Failed to grow memory to %zu cells", length);
+        memset(memory + memory_length, 0, (length - memory_length) * sizeof *memory);
+        memory_length = length;
+    }
+    memory[address] = value;
+    for (int64_t start = address > 3 ? address - 3 : 0; start <= address && start < IMAGE_LENGTH; start++) {
+        if (start + LENGTHS[start] > address) dirty[start] = 1;
+    }
+}
+
+static int64_t jump(int64_t target, int64_t pc) {
+    if (target < 0) fail("Invalid jump target %lld at %lld", (long long) target, (long long) pc);
+    return target;
+}
+
+static int64_t read_input(int64_t pc) {
+    char token[32];
+    size_t length = 0;
+    int c;
+    fflush(stdout);
+    while ((c = getchar()) == ',' || isspace(c)) {
+    }
+    while (c != EOF && c != ',' && !isspace(c)) {
+        if (length + 1 == sizeof token) fail("Input at %lld is too long", (long long) pc);
+        token[length++] = (char) c;
+        c = getchar();
+    }
+    if (length == 0) fail("No input available at %lld", (long long) pc);
+    token[length] = '\0';
+    char *end;
+    errno = 0;
+    long long value = strtoll(token, &end, 10);
+    if (*end != '\0' || errno == ERANGE) fail("Invalid input %s at %lld", token, (long long) pc);
+    return value;
+}
+
+static void write_output(int64_t value) {
+    printf("%lld\n", (long long) value);
+}
+
+static int64_t value(int64_t pc, int64_t code, int index) {
+    int64_t parameter = load(pc + 1 + index);
+    switch (code / PLACES[index] % 10) {
+    case 0:
+        return load(parameter);
+    case 2:
+        return load(relative_base + parameter);
+    default:
+        return parameter;
+    }
+}
+
+static int64_t address(int64_t pc, int64_t code, int index) {
+    int64_t parameter = load(pc + 1 + index);
+    return code / PLACES[index] % 10 == 2 ? relative_base + parameter : parameter;
+}
+
+/* Runs the instruction at pc without any help from the transpiler, returning the address of the
+ * next one, or -1 once the program halts */
+static int64_t interpret(int64_t pc) {
+    int64_t code = load(pc);
+    int parameters, output = -1;
+    switch (code % 100) {
+    case 1: case 2: case 7: case 8:
+        parameters = 3;
+        output = 2;
+        break;
+    case 3:
+        parameters = 1;
+        output = 0;
+        break;
+    case 4: case 9:
+        parameters = 1;
+        break;
+    case 5: case 6:
+        parameters = 2;
+        break;
+    case 99:
+        return -1;
+    default:
+        fail("Invalid operation code %lld at %lld", (long long) code, (long long) pc);
+    }
+    for (int index = 0; index < parameters; index++) {
+        int64_t mode = code / PLACES[index] % 10;
+        if (mode > 2 || (index == output && mode == 1)) {
+            fail("Invalid operation code %lld at %lld", (long long) code, (long long) pc);
+        }
+    }
+    switch (code % 100) {
+    case 1:
+        store(address(pc, code, 2), value(pc, code, 0) + value(pc, code, 1));
+        break;
+    case 2:
+        store(address(pc, code, 2), value(pc, code, 0) * value(pc, code, 1));
+        break;
+    case 3:
+        store(address(pc, code, 0), read_input(pc));
+        break;
+    case 4:
+        write_output(value(pc, code, 0));
+        break;
+    case 5:
+        if (value(pc, code, 0) != 0) return jump(value(pc, code, 1), pc);
+        break;
+    case 6:
+        if (value(pc, code, 0) == 0) return jump(value(pc, code, 1), pc);
+        break;
+    case 7:
+        store(address(pc, code, 2), value(pc, code, 0) < value(pc, code, 1));
+        break;
+    case 8:
+        store(address(pc, code, 2), value(pc, code, 0) == value(pc, code, 1));
+        break;
+    case 9:
+        relative_base += value(pc, code, 0);
+        break;
+    }
+    return pc + 1 + parameters;
+}
+"#;
+
+fn write_c(
+    source: &mut String,
+    program: &[i64],
+    instructions: &BTreeMap<usize, Instruction>,
+) -> std::fmt::Result {
+    let language = Language::C;
+    let labels: BTreeSet<usize> = instructions
+        .values()
+        .flat_map(|instruction| {
+            let next = Some(instruction.next_address()).filter(|_| instruction.falls_through());
+            let target = instruction.jump_target().filter(|_| instruction.can_jump());
+            next.into_iter().chain(target)
+        })
+        .filter(|address| instructions.contains_key(address))
+        .collect();
+    let transfer = |destination: String| match destination.parse::<usize>() {
+        Ok(address) if labels.contains(&address) => format!("goto L{};", address),
+        _ => format!("{{ pc = {}; continue; }}", destination),
+    };
+
+    writeln!(source, "/* Transpiled from an Intcode program */")?;
+    writeln!(source)?;
+    write!(source, "{}", C_PRELUDE)?;
+    writeln!(source)?;
+    // C has no empty arrays, so an empty program gets a placeholder cell past its end
+    let cells = if program.is_empty() {
+        &[0][..]
+    } else {
+        program
+    };
+    writeln!(source, "#define IMAGE_LENGTH {}", program.len())?;
+    writeln!(source)?;
+    writeln!(source, "static const int64_t IMAGE[] = {{")?;
+    write!(source, "{}", image(cells, language))?;
+    writeln!(source, "}};")?;
+    writeln!(source)?;
+    writeln!(
+        source,
+        "/* The number of cells covered by the transpiled instruction at each address */"
+    )?;
+    writeln!(source, "static const unsigned char LENGTHS[] = {{")?;
+    write!(source, "{}", lengths(cells, instructions))?;
+    writeln!(source, "}};")?;
+    writeln!(source)?;
+    write!(source, "{}", C_RUNTIME)?;
+    writeln!(source)?;
+    writeln!(source, "int main(void) {{")?;
+    writeln!(source, "    int64_t pc = 0;")?;
+    writeln!(source, "    memory_length = IMAGE_LENGTH;")?;
+    writeln!(source, "    memory = malloc(sizeof IMAGE);")?;
+    writeln!(source, "    memcpy(memory, IMAGE, sizeof IMAGE);")?;
+    writeln!(source, "    for (;;) {{")?;
+    writeln!(source, "        switch (pc) {{")?;
+    for (address, instruction) in instructions {
+        writeln!(source, "        case {}:", address)?;
+        if labels.contains(address) {
+            writeln!(source, "        L{}:", address)?;
+        }
+        writeln!(
+            source,
+            "            if (dirty[{}]) {{ pc = {}; break; }}",
+            address, address
+        )?;
+        writeln!(source, "            /* {} */", instruction)?;
+        for statement in statements(instruction, language) {
+            writeln!(source, "            {}", statement)?;
+        }
+        if instruction.operation == Operation::Terminate {
+            writeln!(source, "            return 0;")?;
+            continue;
+        }
+        if instruction.can_jump() {
+            let jump = transfer(jump_destination(instruction, language));
+            if instruction.falls_through() {
+                let condition = condition(instruction, language);
+                writeln!(source, "            if ({}) {}", condition, jump)?;
+            } else {
+                writeln!(source, "            {}", jump)?;
+            }
+        }
+        if instruction.falls_through() {
+            let next = transfer(instruction.next_address().to_string());
+            writeln!(source, "            {}", next)?;
+        }
+    }
+    writeln!(source, "        }}")?;
+    writeln!(source, "        pc = interpret(pc);")?;
+    writeln!(source, "        if (pc < 0) return 0;")?;
+    writeln!(source, "    }}")?;
+    writeln!(source, "}}")
+}
+
+const RUST_RUNTIME: &str = r#"const PLACES: [i64; 3] = [100, 1000, 10000];
+
+struct Machine {
+    memory: Vec<i64>,
+    dirty: Vec<bool>,
+    relative_base: i64,
+    input: io::Bytes<io::StdinLock<'static>>,
+}
+
+fn fail(message: String) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}
+
+fn write_output(value: i64) {
+    println!("{}", value);
+}
+
+impl Machine {
+    fn load(&self, address: i64) -> i64 {
+        let index = usize::try_from(address)
+            .unwrap_or_else(|_| fail(format!("Invalid address {}", address)));
+        self.memory.get(index).copied().unwrap_or(0)
+    }
+
+    fn store(&mut self, address: i64, value: i64) {
+        let index = usize::try_from(address)
+            .unwrap_or_else(|_| fail(format!("Invalid address {}", address)));
+        if index >= self.memory.len() {
+            self.memory.resize(index + 1, 0);
+        }
+        self.memory[index] = value;
+        for start in index.saturating_sub(3)..=index {
+            if matches!(LENGTHS.get(start), Some(&length) if start + usize::from(length) > index) {
+                self.dirty[start] = true;
+            }
+        }
+    }
+
+    fn jump(&self, target: i64, pc: usize) -> usize {
+        usize::try_from(target)
+            .unwrap_or_else(|_| fail(format!("Invalid jump target {} at {}", target, pc)))
+    }
+
+    fn read_input(&mut self, pc: usize) -> i64 {
+        let mut token = String::new();
+        for byte in &mut self.input {
+            match byte.unwrap_or_else(|error| fail(format!("Failed to read input: {}", error))) {
+                byte if byte == b',' || byte.is_ascii_whitespace() => {
+                    if !token.is_empty() {
+                        break;
+                    }
+                }
+                _ if token.len() == 31 => fail(format!("Input at {} is too long", pc)),
+                byte => token.push(char::from(byte)),
+            }
+        }
+        if token.is_empty() {
+            fail(format!("No input available at {}", pc));
+        }
+        token
+            .parse()
+            .unwrap_or_else(|_| fail(format!("Invalid input {} at {}", token, pc)))
+    }
+
+    fn value(&self, pc: usize, code: i64, index: usize) -> i64 {
+        let parameter = self.load((pc + 1 + index) as i64);
+        match code / PLACES[index] % 10 {
+            0 => self.load(parameter),
+            2 => self.load(self.relative_base + parameter),
+            _ => parameter,
+        }
+    }
+
+    fn address(&self, pc: usize, code: i64, index: usize) -> i64 {
+        let parameter = self.load((pc + 1 + index) as i64);
+        if code / PLACES[index] % 10 == 2 {
+            self.relative_base + parameter
+        } else {
+            parameter
+        }
+    }
+
+    /// Runs the instruction at `pc` without any help from the transpiler, returning the address
+    /// of the next one, or `None` once the program halts
+    fn interpret(&mut self, pc: usize) -> Option<usize> {
+        let code = self.load(pc as i64);
+        let (parameters, output) = match code % 100 {
+            1 | 2 | 7 | 8 => (3, Some(2)),
+            3 => (1, Some(0)),
+            4 | 9 => (1, None),
+            5 | 6 => (2, None),
+            99 => return None,
+            _ => fail(format!("Invalid operation code {} at {}", code, pc)),
+        };
+        for index in 0..parameters {
+            let mode = code / PLACES[index] % 10;
+            if mode > 2 || (output == Some(index) && mode == 1) {
+                fail(format!("Invalid operation code {} at {}", code, pc));
+            }
+        }
+        match code % 100 {
+            1 => {
+                let value = self.value(pc, code, 0) + self.value(pc, code, 1);
+                self.store(self.address(pc, code, 2), value);
+            }
+            2 => {
+                let value = self.value(pc, code, 0) * self.value(pc, code, 1);
+                self.store(self.address(pc, code, 2), value);
+            }
+            3 => {
+                let input = self.read_input(pc);
+                self.store(self.address(pc, code, 0), input);
+            }
+            4 => write_output(self.value(pc, code, 0)),
+            5 => {
+                if self.value(pc, code, 0) != 0 {
+                    return Some(self.jump(self.value(pc, code, 1), pc));
+                }
+            }
+            6 => {
+                if self.value(pc, code, 0) == 0 {
+                    return Some(self.jump(self.value(pc, code, 1), pc));
+                }
+            }
+            7 => {
+                let value = i64::from(self.value(pc, code, 0) < self.value(pc, code, 1));
+                self.store(self.address(pc, code, 2), value);
+            }
+            8 => {
+                let value = i64::from(self.value(pc, code, 0) == self.value(pc, code, 1));
+                self.store(self.address(pc, code, 2), value);
+            }
+            _ => self.relative_base += self.value(pc, code, 0),
+        }
+        Some(pc + 1 + parameters)
+    }
+}
+"#;
+
+fn write_rust(
+    source: &mut String,
+    program: &[i64],
+    instructions: &BTreeMap<usize, Instruction>,
+) -> std::fmt::Result {
+    let language = Language::Rust;
+
+    writeln!(source, "// Transpiled from an Intcode program")?;
+    writeln!(source)?;
+    writeln!(source, "use std::io::{{self, Read}};")?;
+    writeln!(source, "use std::process;")?;
+    writeln!(source)?;
+    writeln!(source, "const IMAGE: [i64; {}] = [", program.len())?;
+    write!(source, "{}", image(program, language))?;
+    writeln!(source, "];")?;
+    writeln!(source)?;
+    writeln!(
+        source,
+        "/// The number of cells covered by the transpiled instruction at each address"
+    )?;
+    writeln!(source, "const LENGTHS: [u8; {}] = [", program.len())?;
+    write!(source, "{}", lengths(program, instructions))?;
+    writeln!(source, "];")?;
+    writeln!(source)?;
+    write!(source, "{}", RUST_RUNTIME)?;
+    writeln!(source)?;
+    writeln!(source, "fn main() {{")?;
+    writeln!(source, "    let mut machine = Machine {{")?;
+    writeln!(source, "        memory: IMAGE.to_vec(),")?;
+    writeln!(source, "        dirty: vec![false; IMAGE.len()],")?;
+    writeln!(source, "        relative_base: 0,")?;
+    writeln!(source, "        input: io::stdin().lock().bytes(),")?;
+    writeln!(source, "    }};")?;
+    writeln!(source, "    let mut pc: usize = 0;")?;
+    writeln!(source, "    loop {{")?;
+    writeln!(source, "        pc = match pc {{")?;
+    for (address, instruction) in instructions {
+        writeln!(
+            source,
+            "            {} if !machine.dirty[{}] => {{",
+            address, address
+        )?;
+        writeln!(source, "                // {}", instruction)?;
+        for statement in statements(instruction, language) {
+            writeln!(source, "                {}", statement)?;
+        }
+        let next = instruction.next_address().to_string();
+        let tail = if instruction.operation == Operation::Terminate {
+            String::from("return;")
+        } else if !instruction.can_jump() {
+            next
+        } else if !instruction.falls_through() {
+            jump_destination(instruction, language)
+        } else {
+            format!(
+                "if {} {{ {} }} else {{ {} }}",
+                condition(instruction, language),
+                jump_destination(instruction, language),
+                next
+            )
+        };
+        writeln!(source, "                {}", tail)?;
+        writeln!(source, "            }}")?;
+    }
+    writeln!(source, "            _ => match machine.interpret(pc) {{")?;
+    writeln!(source, "                Some(next) => next,")?;
+    writeln!(source, "                None => return,")?;
+    writeln!(source, "            }},")?;
+    writeln!(source, "        }};")?;
+    writeln!(source, "    }}")?;
+    writeln!(source, "}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write as _;
+    use std::path::PathBuf;
+    use std::process::{Command, Stdio};
+
+    use crate::intcode_computer::{ExecutionError, IntcodeComputer};
+    use crate::program::Program;
+    use crate::test_programs::COUNT_DOWN;
+
+    /// A compiled program, whose directory is removed once it is dropped
+    struct Executable {
+        directory: PathBuf,
+        path: PathBuf,
+    }
+
+    impl Drop for Executable {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.directory);
+        }
+    }
+
+    /// Compiles the source with the system compiler, failing the test if there isn't one
+    fn compile(source: &str, language: Language, name: &str) -> Executable {
+        // tests run in parallel, so each program gets a directory of its own
+        let directory = std::env::temp_dir().join(format!(
+            "intcode-transpile-{}-{:?}-{}",
+            std::process::id(),
+            language,
+            name
+        ));
+        std::fs::create_dir_all(&directory).expect("Failed to create a directory");
+        let (compiler, extension) = match language {
+            Language::C => ("cc", "c"),
+            Language::Rust => ("rustc", "rs"),
+        };
+        let path = directory.join(format!("{}.{}", name, extension));
+        std::fs::write(&path, source).expect("Failed to write the source");
+        let executable = Executable {
+            path: directory.join(name),
+            directory,
+        };
+        let mut command = Command::new(compiler);
+        if language == Language::Rust {
+            command.args(["--edition", "2021"]);
+        }
+        let output = command
+            .arg("-O")
+            .arg("-o")
+            .arg(&executable.path)
+            .arg(&path)
+            .output()
+            .unwrap_or_else(|error| panic!("Failed to run {}: {}", compiler, error));
+        assert!(
+            output.status.success(),
+            "Failed to compile {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        executable
+    }
+
+    /// Runs an executable with the input, returning its output and whether it succeeded
+    fn run(executable: &Executable, input: &str) -> (Vec<i64>, bool) {
+        let mut child = Command::new(&executable.path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("Failed to run the executable");
+        child
+            .stdin
+            .take()
+            .expect("Missing stdin")
+            .write_all(input.as_bytes())
+            .expect("Failed to write the input");
+        let output = child.wait_with_output().expect("Failed to wait");
+        let values = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| line.parse().expect("Failed to parse an output"))
+            .collect();
+        (values, output.status.success())
+    }
+
+    fn interpret(program: &[i64], input: &[i64]) -> Vec<i64> {
+        let input = input.iter().copied().collect();
+        let mut computer = IntcodeComputer::new_with_input(program.to_vec(), input);
+        computer
+            .run_to_halt()
+            .expect("Failed to run the program")
+            .into()
+    }
+
+    /// Checks the transpiled program against the interpreter for each of the inputs
+    fn assert_matches_interpreter(program: &[i64], inputs: &[&[i64]], name: &str) {
+        for &language in &[Language::C, Language::Rust] {
+            let source = transpile(program, language);
+            let executable = compile(&source, language, name);
+            for input in inputs {
+                let text: Vec<String> = input.iter().map(ToString::to_string).collect();
+                let (output, success) = run(&executable, &text.join(","));
+                assert!(success, "{} failed in {:?}", name, language);
+                assert_eq!(
+                    interpret(program, input),
+                    output,
+                    "{} differs in {:?}",
+                    name,
+                    language
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn it_should_parse_languages() {
+        assert_eq!(Ok(Language::C), "c".parse());
+        assert_eq!(Ok(Language::Rust), "Rust".parse());
+        assert!("cobol".parse::<Language>().is_err());
+    }
+
+    #[test]
+    fn it_should_jump_directly_to_known_targets() {
+        let source = transpile(&COUNT_DOWN, Language::C);
+        assert!(source.contains(
+            "        case 0:
+        L0:
+            if (dirty[0]) { pc = 0; break; }
+            /* ADD  [8], #-1, [8] */
+            store(8, load(8) + -1);
+            goto L4;
+"
+        ));
+        assert!(source.contains(
+            "        case 4:
+        L4:
+            if (dirty[4]) { pc = 4; break; }
+            /* JNZ  [8], #0 */
+            if (load(8) != 0) goto L0;
+            goto L7;
+"
+        ));
+        // the misaligned EQ at 5 runs off of the end of the program
+        assert!(source.contains("            { pc = 9; continue; }\n"));
+
+        let source = transpile(&COUNT_DOWN, Language::Rust);
+        assert!(source.contains(
+            "            4 if !machine.dirty[4] => {
+                // JNZ  [8], #0
+                if machine.load(8) != 0 { 0 } else { 7 }
+            }
+"
+        ));
+    }
+
+    #[test]
+    fn it_should_transpile_an_empty_program() {
+        assert!(
+            transpile(&[], Language::C).contains("static const int64_t IMAGE[] = {\n    0,\n};")
+        );
+        assert_eq!(
+            Err(ExecutionError::InvalidOperationCode { index: 0, code: 0 }),
+            IntcodeComputer::new(Vec::new()).execute()
+        );
+        for &language in &[Language::C, Language::Rust] {
+            let executable = compile(&transpile(&[], language), language, "empty");
+            assert_eq!((Vec::new(), false), run(&executable, ""));
+        }
+    }
+
+    #[test]
+    fn it_should_reject_invalid_input() {
+        // echoes its input
+        let program = [3, 0, 4, 0, 99];
+        for &language in &[Language::C, Language::Rust] {
+            let executable = compile(&transpile(&program, language), language, "echo");
+            assert_eq!((vec![-42], true), run(&executable, "-42"));
+            assert_eq!((Vec::new(), false), run(&executable, "12ab"));
+            assert_eq!(
+                (Vec::new(), false),
+                run(&executable, "99999999999999999999")
+            );
+            let overlong = format!("1{}", "0".repeat(40));
+            assert_eq!((Vec::new(), false), run(&executable, &overlong));
+            let padded = format!("{}7", "0".repeat(30));
+            assert_eq!((vec![7], true), run(&executable, &padded));
+            let padded = format!("{}7", "0".repeat(31));
+            assert_eq!((Vec::new(), false), run(&executable, &padded));
+        }
+    }
+
+    #[test]
+    fn it_should_match_the_interpreter_on_the_day_five_program() {
+        let program: Vec<i64> = Program::from_file("data/day-five-input.txt")
+            .expect("Failed to load the day five program")
+            .into_words();
+        assert_matches_interpreter(&program, &[&[1], &[5]], "day_five");
+    }
+
+    #[test]
+    fn it_should_match_the_interpreter_on_relative_addressing() {
+        // outputs a copy of itself
+        let quine = [
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
+        assert_matches_interpreter(&quine, &[&[]], "quine");
+    }
+
+    #[test]
+    fn it_should_interpret_instructions_that_were_written_over() {
+        // patches the parameter of the OUT at 4 before it runs, and then the operation code of
+        // the ADD at 0 so that the jump back to it multiplies instead
+        let program = [
+            1101, 3, 7, 5, 104, 0, 1101, 0, 1102, 0, 1007, 5, 20, 20, 1005, 20, 0, 99, 0, 0, 0,
+        ];
+        assert_eq!(vec![10, 21], interpret(&program, &[]));
+        assert_matches_interpreter(&program, &[&[]], "patched");
+    }
+}